@@ -1,102 +1,505 @@
-use crate::cache::cache_operations::{CacheResponse, InvalidationPattern, RequestCached};
+use crate::cache::cache_operations::{
+    BackgroundGenerator, CacheResponse, InvalidationPattern, RequestCached,
+};
+use crate::cache::compression::{compress, decompress};
+use crate::cache::hot_keys::HotKeys;
 use crate::cache::inner_cache::CachedWithCode;
-use crate::cache::{Cache, CACHE_REQS_PREFIX, CACHE_RESP_PREFIX};
+use crate::cache::keys::{checksum_addresses, normalize_key};
+use crate::cache::metrics::{chain_id_from_key, chain_label, key_family};
+use crate::cache::single_flight::SingleFlight;
+use crate::cache::soft_expiry::SoftExpiring;
+use crate::cache::tags::tags_for_key;
+use crate::cache::{Cache, CACHE_LOCK_PREFIX, CACHE_REQS_PREFIX, CACHE_RESP_PREFIX};
+use crate::config::{
+    self, cache_invalidation_scan_fallback, cache_schema_version, refresh_ahead_hits,
+    refresh_ahead_percentage, refresh_ahead_tracked_keys, refresh_ahead_window,
+    request_coalescing_distributed, request_coalescing_enabled, request_coalescing_poll_interval,
+};
+use crate::monitoring::metrics::{record_cache_lookup, CacheOutcome};
 use crate::utils::errors::{ApiError, ApiResult, ErrorDetails};
 use crate::utils::http_client::Request;
+use lazy_static::lazy_static;
 use rocket::response::content;
+use rocket::tokio::time::{sleep, Instant};
 use serde::Serialize;
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+lazy_static! {
+    static ref IN_FLIGHT_REQUESTS: SingleFlight = SingleFlight::new();
+    static ref REFRESHING_KEYS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    static ref HOT_KEYS: HotKeys = HotKeys::new(refresh_ahead_tracked_keys());
+}
+
 pub(super) async fn invalidate(cache: Arc<dyn Cache>, pattern: &InvalidationPattern) {
+    let pattern_string = key_pattern(pattern);
+    match pattern.to_tag() {
+        Some(tag) => {
+            cache.invalidate_tagged(&tag, &pattern_string).await;
+            // Entries cached before keys were tagged can only be found by scanning
+            if cache_invalidation_scan_fallback() {
+                cache.invalidate_pattern(&pattern_string).await
+            }
+        }
+        // Free form values cannot be tagged, so we fall back to scanning the keyspace
+        None => cache.invalidate_pattern(&pattern_string).await,
+    }
+}
+
+// Looks keys up the way [invalidate] does, so they are the ones it would delete
+pub(super) async fn matching_keys(
+    cache: Arc<dyn Cache>,
+    pattern: &InvalidationPattern,
+    limit: usize,
+) -> Vec<String> {
+    let pattern_string = key_pattern(pattern);
+    match pattern.to_tag() {
+        Some(tag) => {
+            let mut keys = cache.tagged_keys(&tag, &pattern_string, limit).await;
+            if cache_invalidation_scan_fallback() {
+                for key in cache.keys(&pattern_string, limit).await {
+                    if keys.len() == limit {
+                        break;
+                    }
+                    if !keys.contains(&key) {
+                        keys.push(key);
+                    }
+                }
+            }
+            keys
+        }
+        None => cache.keys(&pattern_string, limit).await,
+    }
+}
+
+// Response cache keys hold checksummed addresses (see [normalize_key]), like the upstream URLs
+// built from checksummed request paths
+fn key_pattern(pattern: &InvalidationPattern) -> String {
+    checksum_addresses(&pattern.to_pattern_string())
+}
+
+// Cache entries written here are registered under their tags, so they can be invalidated without
+// scanning the keyspace. They carry the end of their `duration` (see [SoftExpiring]), which tells
+// clients how long they may reuse them, and outlive it by their stale window.
+async fn create_tagged(
+    cache: &Arc<dyn Cache>,
+    cache_key: &str,
+    value: &str,
+    duration: usize,
+    stale_window: usize,
+) {
+    let value = SoftExpiring::wrap(value, duration);
     cache
-        .invalidate_pattern(pattern.to_pattern_string().as_str())
+        .create_tagged(
+            cache_key,
+            &compress(&value),
+            &tags_for_key(cache_key),
+            duration + stale_window,
+        )
         .await;
 }
 
+// Values written through [create_tagged] may be compressed
+async fn fetch(cache: &Arc<dyn Cache>, cache_key: &str) -> Option<SoftExpiring> {
+    decompress(cache.fetch_bytes(cache_key).await?).map(SoftExpiring::parse)
+}
+
 pub(super) async fn cache_response<S>(
     cache_response: &CacheResponse<'_, S>,
 ) -> ApiResult<content::RawJson<String>>
+where
+    S: Serialize,
+{
+    let started = Instant::now();
+    let cache_key = response_cache_key(&cache_response.key, &cache_response.query_defaults);
+    let (outcome, result, fresh_for) = lookup_response(cache_response, &cache_key).await;
+    record_lookup(
+        "response",
+        &cache_key,
+        cache_response.chain_id.as_deref(),
+        outcome,
+        started,
+    );
+    if let Ok(body) = &result {
+        let hints = &cache_response.response_hints;
+        hints.etag_from_body(&body.0);
+        hints.cache_for(fresh_for, cache_response.stale_while_revalidate);
+        if cache_response.public {
+            hints.public();
+        }
+    }
+    result
+}
+
+// Responses are serialized gateway models, so they are only shared between releases using the
+// same models
+pub(super) fn response_cache_key(key: &str, query_defaults: &[(String, String)]) -> String {
+    format!(
+        "{}_{}_{}",
+        CACHE_RESP_PREFIX,
+        cache_schema_version(),
+        normalize_key(key, query_defaults)
+    )
+}
+
+// Along with the response, returns how long it stays fresh in milliseconds
+async fn lookup_response<S>(
+    cache_response: &CacheResponse<'_, S>,
+    cache_key: &str,
+) -> (CacheOutcome, ApiResult<content::RawJson<String>>, usize)
 where
     S: Serialize,
 {
     let cache = cache_response.cache.clone();
-    let cache_key = format!("{}_{}", CACHE_RESP_PREFIX, cache_response.key);
-    let cached = cache.fetch(&cache_key).await;
-    match cached {
-        Some(value) => Ok(content::RawJson(value)),
-        None => {
-            let resp_string = serde_json::to_string(&cache_response.generate().await?)?;
-            cache
-                .create(&cache_key, &resp_string, cache_response.duration)
-                .await;
-            Ok(content::RawJson(resp_string))
+    let duration = cache_response.duration;
+    match fetch(&cache, cache_key).await {
+        None => with_outcome(generate_response(cache_response, cache_key).await, duration),
+        Some(cached) => {
+            if !cached.is_stale() {
+                let fresh_for = cached.fresh_for().unwrap_or(0);
+                return (
+                    CacheOutcome::Hit,
+                    Ok(content::RawJson(cached.value)),
+                    fresh_for,
+                );
+            }
+            if let Some(generator) = &cache_response.background_generator {
+                spawn_regeneration(cache_response, generator, cache_key);
+                return (CacheOutcome::Stale, Ok(content::RawJson(cached.value)), 0);
+            }
+            match generate_response(cache_response, cache_key).await {
+                Err(error)
+                    if error.status >= 500
+                        && cached.within_stale_if_error(cache_response.stale_if_error) =>
+                {
+                    (CacheOutcome::Stale, Ok(content::RawJson(cached.value)), 0)
+                }
+                result => with_outcome(result, duration),
+            }
         }
     }
 }
 
+fn spawn_regeneration<S>(
+    cache_response: &CacheResponse<'_, S>,
+    generator: &BackgroundGenerator,
+    cache_key: &str,
+) where
+    S: Serialize,
+{
+    let generator = generator.clone();
+    let cache = cache_response.cache.clone();
+    let duration = cache_response.duration;
+    let stale_window = cache_response.stale_window();
+    let key = cache_key.to_string();
+    let refresh = async move {
+        match generator().await {
+            Ok(body) => create_tagged(&cache, &key, &body, duration, stale_window).await,
+            Err(error) => log::warn!("Background refresh of {} failed: {}", &key, error),
+        }
+    };
+    spawn_refresh_task(
+        &cache_response.cache,
+        cache_key,
+        cache_response.request_timeout as usize,
+        refresh,
+    );
+}
+
+async fn generate_response<S>(
+    cache_response: &CacheResponse<'_, S>,
+    cache_key: &str,
+) -> ApiResult<content::RawJson<String>>
+where
+    S: Serialize,
+{
+    let resp_string = serde_json::to_string(&cache_response.generate().await?)?;
+    create_tagged(
+        &cache_response.cache,
+        cache_key,
+        &resp_string,
+        cache_response.duration,
+        cache_response.stale_window(),
+    )
+    .await;
+    Ok(content::RawJson(resp_string))
+}
+
 pub(super) async fn request_cached(operation: &RequestCached) -> ApiResult<String> {
-    let cache = operation.cache.clone();
-    let client = operation.client.clone();
+    let started = Instant::now();
+    // Upstream URLs are never normalized: the upstream may reject equivalent forms of them, e.g.
+    // addresses which are not checksummed
     let cache_key = format!("{}_{}", CACHE_REQS_PREFIX, &operation.url);
-    match cache.fetch(&cache_key).await {
-        Some(cached) => CachedWithCode::split(&cached).to_result(),
-        None => {
-            let http_request = {
-                let mut request = Request::new(String::from(&operation.url));
-                request.timeout(Duration::from_millis(operation.request_timeout));
-                for (header, value) in &operation.headers {
-                    request.add_header((header, value));
-                }
-                request
-            };
-            let response = client.get(http_request).await;
-
-            match response {
-                Err(error) => {
-                    let default_message: String = String::from("Unknown error");
-                    let response_body: &String =
-                        error.details.message.as_ref().unwrap_or(&default_message);
-                    // TODO extract http error range check (client error vs server error)
-                    let is_client_error = error.status >= 400 && error.status < 500;
-
-                    // If cache_all_errors is enabled we cache both client and server errors
-                    // else we just cache client errors
-                    if is_client_error || operation.cache_all_errors {
-                        cache
-                            .create(
-                                &cache_key,
-                                &CachedWithCode::join(error.status, &response_body),
-                                operation.error_cache_duration,
-                            )
-                            .await;
-                    }
+    let (outcome, result, fresh_for) = lookup_request(operation, &cache_key).await;
+    record_lookup(
+        "request",
+        &cache_key,
+        operation.chain_id.as_deref(),
+        outcome,
+        started,
+    );
+    if let (Ok(_), Some(hints)) = (&result, &operation.response_hints) {
+        hints.cache_for(fresh_for, operation.stale_while_revalidate);
+    }
+    result
+}
 
-                    if let Some(error_details) =
-                        serde_json::from_str::<ErrorDetails>(response_body).ok()
-                    {
-                        Err(ApiError::new(error.status, error_details))
-                    } else {
-                        Err(ApiError::new_from_message_with_code(
-                            error.status,
-                            String::from(response_body),
-                        ))
-                    }
-                }
-                Ok(response) => {
-                    let status_code = response.status_code;
-                    let response_body = response.body;
-
-                    cache
-                        .create(
-                            &cache_key,
-                            &CachedWithCode::join(status_code, &response_body),
-                            operation.cache_duration,
-                        )
-                        .await;
-                    Ok(response_body.to_string())
+// Along with the response, returns how long it stays fresh in milliseconds
+async fn lookup_request(
+    operation: &RequestCached,
+    cache_key: &str,
+) -> (CacheOutcome, ApiResult<String>, usize) {
+    let cache = operation.cache.clone();
+    let duration = operation.cache_duration;
+    match fetch(&cache, cache_key).await {
+        None => with_outcome(request_fresh(operation, cache_key).await, duration),
+        Some(cached) => {
+            let fresh_for = cached.fresh_for();
+            let stale = cached.is_stale();
+            let serve_if_error = cached.within_stale_if_error(operation.stale_if_error);
+            let cached = CachedWithCode::split(&cached.value);
+            // Errors are cached for their own duration and are never served stale
+            if cached.is_error() {
+                return (CacheOutcome::CachedError, cached.to_result(), 0);
+            }
+            if !stale {
+                refresh_ahead(operation, cache_key, fresh_for);
+                return (
+                    CacheOutcome::Hit,
+                    cached.to_result(),
+                    fresh_for.unwrap_or(0),
+                );
+            }
+            if operation.stale_while_revalidate > 0 {
+                spawn_refresh(operation, cache_key);
+                return (CacheOutcome::Stale, cached.to_result(), 0);
+            }
+            match request_fresh(operation, cache_key).await {
+                Err(error) if error.status >= 500 && serve_if_error => {
+                    (CacheOutcome::Stale, cached.to_result(), 0)
                 }
+                result => with_outcome(result, duration),
             }
         }
     }
 }
+
+fn with_outcome<T>(result: ApiResult<T>, fresh_for: usize) -> (CacheOutcome, ApiResult<T>, usize) {
+    let outcome = if result.is_ok() {
+        CacheOutcome::Miss
+    } else {
+        CacheOutcome::Error
+    };
+    (outcome, result, fresh_for)
+}
+
+fn record_lookup(
+    operation: &str,
+    cache_key: &str,
+    chain_id: Option<&str>,
+    outcome: CacheOutcome,
+    started: Instant,
+) {
+    let chain_id = chain_id.or_else(|| chain_id_from_key(cache_key));
+    record_cache_lookup(
+        operation,
+        key_family(cache_key),
+        chain_label(chain_id, &config::current().metrics_chain_ids),
+        outcome,
+        started.elapsed(),
+    );
+}
+
+async fn fetch_fresh(cache: &Arc<dyn Cache>, cache_key: &str) -> Option<String> {
+    let cached = fetch(cache, cache_key).await?;
+    if CachedWithCode::split(&cached.value).is_error() || !cached.is_stale() {
+        Some(cached.value)
+    } else {
+        None
+    }
+}
+
+// Entries read at least [refresh_ahead_hits] times per [refresh_ahead_window] are refreshed in the
+// background shortly before they turn stale, so they never go missing while traffic is steady.
+// `fresh_for` comes with the cached value, so telling whether it is due takes no round trip.
+fn refresh_ahead(operation: &RequestCached, cache_key: &str, fresh_for: Option<usize>) {
+    let threshold = refresh_ahead_hits();
+    let window = Duration::from_millis(refresh_ahead_window());
+    if threshold == 0 || !HOT_KEYS.record(cache_key, threshold, window) {
+        return;
+    }
+    let refresh_window = operation.cache_duration * refresh_ahead_percentage() / 100;
+    if fresh_for.map_or(false, |fresh_for| fresh_for <= refresh_window) {
+        spawn_refresh(operation, cache_key);
+    }
+}
+
+fn spawn_refresh(operation: &RequestCached, cache_key: &str) {
+    let refreshed = operation.clone();
+    let key = cache_key.to_string();
+    let refresh = async move {
+        if let Err(error) = request_upstream(&refreshed, &key).await {
+            log::warn!("Background refresh of {} failed: {}", &key, error);
+        }
+    };
+    spawn_refresh_task(
+        &operation.cache,
+        cache_key,
+        operation.request_timeout as usize,
+        refresh,
+    );
+}
+
+// Runs `refresh` in the background unless `cache_key` is already being refreshed: by this process,
+// as told by [RefreshClaim] without spawning a task, or by another instance (or a coalesced
+// request, see [request_coalesced]), as told by the lock in the shared cache
+fn spawn_refresh_task<F>(cache: &Arc<dyn Cache>, cache_key: &str, lock_timeout: usize, refresh: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let claim = match RefreshClaim::acquire(cache_key) {
+        Some(claim) => claim,
+        None => return,
+    };
+    let cache = cache.clone();
+    let lock_key = format!("{}_{}", CACHE_LOCK_PREFIX, cache_key);
+    config::spawn(async move {
+        let _claim = claim;
+        let lock_token = format!("{:016x}", rand::random::<u64>());
+        if cache.try_lock(&lock_key, &lock_token, lock_timeout).await {
+            refresh.await;
+            cache.unlock(&lock_key, &lock_token).await;
+        }
+    });
+}
+
+// Marks a key as being refreshed by this process until dropped
+struct RefreshClaim(String);
+
+impl RefreshClaim {
+    fn acquire(cache_key: &str) -> Option<Self> {
+        let mut refreshing = REFRESHING_KEYS
+            .lock()
+            .expect("Refreshing keys lock poisoned");
+        if refreshing.insert(cache_key.to_string()) {
+            Some(RefreshClaim(cache_key.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for RefreshClaim {
+    fn drop(&mut self) {
+        REFRESHING_KEYS
+            .lock()
+            .expect("Refreshing keys lock poisoned")
+            .remove(&self.0);
+    }
+}
+
+async fn request_fresh(operation: &RequestCached, cache_key: &str) -> ApiResult<String> {
+    if request_coalescing_enabled() {
+        IN_FLIGHT_REQUESTS
+            .execute(cache_key, || request_coalesced(operation, cache_key))
+            .await
+    } else {
+        request_upstream(operation, cache_key).await
+    }
+}
+
+// Only one caller per process gets here for a given key at a time. With distributed coalescing
+// enabled, the caller additionally competes for a short-lived lock in the shared cache, and if
+// another instance holds it, waits for that instance to populate the entry.
+async fn request_coalesced(operation: &RequestCached, cache_key: &str) -> ApiResult<String> {
+    let cache = operation.cache.clone();
+    // The entry may have been populated while we were waiting for our turn
+    if let Some(cached) = fetch_fresh(&cache, cache_key).await {
+        return CachedWithCode::split(&cached).to_result();
+    }
+    if !request_coalescing_distributed() {
+        return request_upstream(operation, cache_key).await;
+    }
+
+    let lock_key = format!("{}_{}", CACHE_LOCK_PREFIX, cache_key);
+    let lock_token = format!("{:016x}", rand::random::<u64>());
+    let lock_duration = operation.request_timeout as usize;
+    if cache.try_lock(&lock_key, &lock_token, lock_duration).await {
+        let result = request_upstream(operation, cache_key).await;
+        cache.unlock(&lock_key, &lock_token).await;
+        return result;
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(operation.request_timeout);
+    while Instant::now() < deadline {
+        sleep(Duration::from_millis(request_coalescing_poll_interval())).await;
+        if let Some(cached) = fetch_fresh(&cache, cache_key).await {
+            return CachedWithCode::split(&cached).to_result();
+        }
+        if !cache.has_key(&lock_key).await {
+            break;
+        }
+    }
+    // The lock holder finished without caching a result (e.g. a server error) or timed out
+    request_upstream(operation, cache_key).await
+}
+
+async fn request_upstream(operation: &RequestCached, cache_key: &str) -> ApiResult<String> {
+    let cache = operation.cache.clone();
+    let client = operation.client.clone();
+    let http_request = {
+        let mut request = Request::new(String::from(&operation.url));
+        request.timeout(Duration::from_millis(operation.request_timeout));
+        for (header, value) in &operation.headers {
+            request.add_header((header, value));
+        }
+        request
+    };
+    let response = client.get(http_request).await;
+
+    match response {
+        // Not answered by the upstream, so there is nothing to cache
+        Err(error) if error.is_upstream_unavailable() => Err(error),
+        Err(error) => {
+            let default_message: String = String::from("Unknown error");
+            let response_body: &String = error.details.message.as_ref().unwrap_or(&default_message);
+            // Server errors are never cached when a stale response can be served instead
+            let error_cache_duration = operation.error_cache_duration_for(error.status);
+            if error_cache_duration > 0 {
+                create_tagged(
+                    &cache,
+                    cache_key,
+                    &CachedWithCode::join(error.status, &response_body),
+                    error_cache_duration,
+                    0,
+                )
+                .await;
+            }
+
+            if let Some(error_details) = serde_json::from_str::<ErrorDetails>(response_body).ok() {
+                Err(ApiError::new(error.status, error_details))
+            } else {
+                Err(ApiError::new_from_message_with_code(
+                    error.status,
+                    String::from(response_body),
+                ))
+            }
+        }
+        Ok(response) => {
+            let status_code = response.status_code;
+            let response_body = response.body;
+
+            create_tagged(
+                &cache,
+                cache_key,
+                &CachedWithCode::join(status_code, &response_body),
+                operation.cache_duration,
+                operation.stale_window(),
+            )
+            .await;
+            Ok(response_body.to_string())
+        }
+    }
+}
@@ -7,14 +7,17 @@ use rocket::futures::FutureExt;
 use rocket::response::content;
 use serde::{Deserialize, Serialize};
 
-use crate::cache::cache_op_executors::{cache_response, invalidate, request_cached};
+use crate::cache::cache_op_executors::{cache_response, invalidate, matching_keys, request_cached};
+use crate::cache::compression::decompress;
+use crate::cache::error_policy::{ErrorCachePolicy, ErrorStatus};
+use crate::cache::inner_cache::CachedWithCode;
 use crate::cache::manager::ChainCache;
+use crate::cache::soft_expiry::SoftExpiring;
+use crate::cache::tags::{entity_tag, identifier_tag};
 use crate::cache::{Cache, CACHE_REQS_PREFIX, CACHE_REQS_RESP_PREFIX, CACHE_RESP_PREFIX};
-use crate::config::{
-    base_config_service_uri, default_request_timeout, request_cache_duration,
-    request_error_cache_duration,
-};
+use crate::config::{self, base_config_service_uri, Config};
 use crate::providers::info::generate_token_key;
+use crate::utils::cache_headers::ResponseHints;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
 use crate::utils::http_client::HttpClient;
@@ -81,6 +84,32 @@ impl InvalidationPattern {
             }
         }
     }
+
+    /// Tag narrowing down the keys matched by [InvalidationPattern::to_pattern_string], if the
+    /// pattern targets a taggable value (an address, a hash or an entity type)
+    pub(super) fn to_tag(&self) -> Option<String> {
+        match &self {
+            InvalidationPattern::Any(_, value)
+            | InvalidationPattern::Balances(_, value)
+            | InvalidationPattern::Collectibles(_, value)
+            | InvalidationPattern::Transfers(_, value)
+            | InvalidationPattern::Transactions(_, value) => identifier_tag(value),
+            InvalidationPattern::Contracts => Some(entity_tag("contracts")),
+            InvalidationPattern::Chains => Some(entity_tag("chains")),
+            InvalidationPattern::Tokens { .. } => None,
+        }
+    }
+
+    /// Whether the pattern may match data prefetched by [crate::providers::warm_up]: chain
+    /// configurations, safe apps, master copies and token lists, none of which are keyed by an
+    /// address or hash
+    pub fn matches_warmed_up_data(&self) -> bool {
+        match &self {
+            InvalidationPattern::Chains | InvalidationPattern::Tokens { .. } => true,
+            InvalidationPattern::Any(_, value) => identifier_tag(value).is_none(),
+            _ => false,
+        }
+    }
 }
 
 impl InvalidationScope {
@@ -101,8 +130,35 @@ impl Invalidate {
     pub async fn execute(&self) {
         invalidate(self.cache.clone(), &self.pattern).await
     }
+
+    /// Up to `limit` of the keys [Invalidate::execute] would delete, without deleting them
+    pub async fn matching_keys(&self, limit: usize) -> Vec<String> {
+        matching_keys(self.cache.clone(), &self.pattern, limit).await
+    }
+}
+
+/// Splits a cached value into the status code of the upstream response it was stored with, if
+/// any, and its body. Only upstream responses ([RequestCached]) are stored with a status code.
+/// Compressed values are decompressed, or returned lossily as text if that fails.
+pub fn decode_cached_value(key: &str, value: Vec<u8>) -> (Option<u16>, String) {
+    let value =
+        decompress(value.clone()).unwrap_or_else(|| String::from_utf8_lossy(&value).into_owned());
+    let value = &SoftExpiring::parse(value).value;
+    let cached = key
+        .starts_with(CACHE_REQS_PREFIX)
+        .then(|| CachedWithCode::try_split(value))
+        .flatten();
+    match cached {
+        Some(cached) => (Some(cached.code), cached.data),
+        None => (None, value.to_string()),
+    }
 }
 
+/// Generates a serialized response independently of the request it was created for, so that it
+/// can run after the request was answered
+pub(super) type BackgroundGenerator =
+    Arc<dyn Fn() -> BoxFuture<'static, ApiResult<String>> + Send + Sync>;
+
 pub struct CacheResponse<'a, R>
 where
     R: Serialize,
@@ -110,8 +166,17 @@ where
     pub(super) cache: Arc<dyn Cache>,
     pub key: String,
     pub duration: usize,
+    pub stale_while_revalidate: usize,
+    pub stale_if_error: usize,
+    pub chain_id: Option<String>,
+    pub public: bool,
+    pub query_defaults: Vec<(String, String)>,
+    // Upstream time out of the chain, for as long as a background regeneration holds its lock
+    pub(super) request_timeout: u64,
+    pub(super) response_hints: ResponseHints,
     // "dyn" allows setting the type of the BoxFuture to different times in runtime
     pub resp_generator: Option<Box<dyn Fn() -> BoxFuture<'a, ApiResult<R>> + Send + Sync + 'a>>,
+    pub(super) background_generator: Option<BackgroundGenerator>,
 }
 
 impl<'a, R> CacheResponse<'a, R>
@@ -119,11 +184,23 @@ where
     R: Serialize,
 {
     pub fn new(context: &RequestContext, chain_cache: ChainCache) -> Self {
+        let config = chain_cache.chain_id().map_or_else(
+            || context.config(),
+            |chain_id| context.chain_config(chain_id),
+        );
         CacheResponse {
             key: context.request_id.to_string(),
+            duration: config.request_cache_duration,
+            request_timeout: config.default_request_timeout,
+            chain_id: chain_cache.chain_id().map(String::from),
             cache: context.cache(chain_cache),
-            duration: request_cache_duration(),
+            response_hints: context.response_hints(),
+            stale_while_revalidate: 0,
+            stale_if_error: 0,
+            public: false,
+            query_defaults: vec![],
             resp_generator: None,
+            background_generator: None,
         }
     }
 
@@ -132,6 +209,45 @@ where
         self
     }
 
+    /// Keeps serving the cached response for up to `duration` after it expired, while `generator`
+    /// regenerates it in the background. Unlike [CacheResponse::resp_generator], `generator`
+    /// cannot borrow request scoped data (see [RequestContext::detach]).
+    pub fn stale_while_revalidate<F, Fut>(&mut self, duration: usize, generator: F) -> &mut Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ApiResult<R>> + Send + 'static,
+        R: 'static,
+    {
+        self.stale_while_revalidate = duration;
+        self.background_generator = (duration > 0).then(|| background_generator(generator));
+        self
+    }
+
+    /// Serves the cached response for up to `duration` after it expired if generating a fresh
+    /// one fails with a server error
+    pub fn stale_if_error(&mut self, duration: usize) -> &mut Self {
+        self.stale_if_error = duration;
+        self
+    }
+
+    pub(super) fn stale_window(&self) -> usize {
+        self.stale_while_revalidate.max(self.stale_if_error)
+    }
+
+    /// Declares `value` as the default of the query parameter `name`, so requests setting it
+    /// explicitly share the cache entry of those omitting it
+    pub fn query_default(&mut self, name: &str, value: &str) -> &mut Self {
+        self.query_defaults
+            .push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// Lets CDNs and other shared caches store the response, see [ResponseHints::public]
+    pub fn public(&mut self) -> &mut Self {
+        self.public = true;
+        self
+    }
+
     pub fn resp_generator<F, Fut>(&mut self, resp_generator: F) -> &mut Self
     where
         F: Fn() -> Fut + Send + Sync + 'a,
@@ -150,45 +266,95 @@ where
     }
 }
 
+fn background_generator<F, Fut, R>(generator: F) -> BackgroundGenerator
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ApiResult<R>> + Send + 'static,
+    R: Serialize + 'static,
+{
+    Arc::new(move || {
+        let response = generator();
+        async move { Ok(serde_json::to_string(&response.await?)?) }.boxed()
+    })
+}
+
+#[derive(Clone)]
 pub struct RequestCached {
     pub(super) client: Arc<dyn HttpClient>,
     pub(super) cache: Arc<dyn Cache>,
     pub url: String,
     pub request_timeout: u64,
     pub cache_duration: usize,
-    pub error_cache_duration: usize,
-    pub cache_all_errors: bool,
+    pub error_policy: ErrorCachePolicy,
+    pub stale_while_revalidate: usize,
+    pub stale_if_error: usize,
+    pub chain_id: Option<String>,
     pub headers: HashMap<String, String>,
+    pub(super) response_hints: Option<ResponseHints>,
 }
 
 impl RequestCached {
     pub fn new(url: String, client: &Arc<dyn HttpClient>, cache: &Arc<dyn Cache>) -> Self {
-        RequestCached {
-            client: client.clone(),
-            cache: cache.clone(),
+        Self::with_defaults(url, client, cache, None, &config::current())
+    }
+
+    /// [RequestCached::new] for a request of the chain `chain_id`, with the timeout and cache
+    /// duration of the chain as defaults (see [config::for_chain])
+    pub fn new_for_chain(
+        url: String,
+        client: &Arc<dyn HttpClient>,
+        cache: &Arc<dyn Cache>,
+        chain_id: &str,
+    ) -> Self {
+        Self::with_defaults(
             url,
-            request_timeout: default_request_timeout(),
-            cache_duration: request_cache_duration(),
-            error_cache_duration: request_error_cache_duration(),
-            cache_all_errors: false,
-            headers: HashMap::default(),
-        }
+            client,
+            cache,
+            Some(chain_id),
+            &config::for_chain(chain_id),
+        )
     }
 
     pub fn new_from_context(
         url: String,
         context: &RequestContext,
         chain_cache: ChainCache,
+    ) -> Self {
+        let chain_id = chain_cache.chain_id().map(String::from);
+        let config = chain_id.as_deref().map_or_else(
+            || context.config(),
+            |chain_id| context.chain_config(chain_id),
+        );
+        let mut request = Self::with_defaults(
+            url,
+            &context.http_client(),
+            &context.cache(chain_cache),
+            chain_id.as_deref(),
+            &config,
+        );
+        request.response_hints = Some(context.response_hints());
+        request
+    }
+
+    fn with_defaults(
+        url: String,
+        client: &Arc<dyn HttpClient>,
+        cache: &Arc<dyn Cache>,
+        chain_id: Option<&str>,
+        config: &Config,
     ) -> Self {
         RequestCached {
-            client: context.http_client(),
-            cache: context.cache(chain_cache),
+            client: client.clone(),
+            cache: cache.clone(),
             url,
-            request_timeout: default_request_timeout(),
-            cache_duration: request_cache_duration(),
-            error_cache_duration: request_error_cache_duration(),
-            cache_all_errors: false,
+            request_timeout: config.default_request_timeout,
+            cache_duration: config.request_cache_duration,
+            error_policy: config.error_cache_policy.clone(),
+            stale_while_revalidate: 0,
+            stale_if_error: 0,
+            chain_id: chain_id.map(String::from),
             headers: HashMap::new(),
+            response_hints: None,
         }
     }
 
@@ -202,16 +368,49 @@ impl RequestCached {
         self
     }
 
+    /// Caches client errors (4xx) for `error_cache_duration`, unless the policy has an entry for
+    /// their specific status code. Server errors and timeouts keep the durations of the policy,
+    /// see [RequestCached::cache_errors] to override those.
     pub fn error_cache_duration(&mut self, error_cache_duration: usize) -> &mut Self {
-        self.error_cache_duration = error_cache_duration;
+        self.error_policy
+            .set(ErrorStatus::Class(4), error_cache_duration);
         self
     }
 
-    pub fn cache_all_errors(&mut self) -> &mut Self {
-        self.cache_all_errors = true;
+    /// Caches errors matching `status` for `duration`, overriding the global policy. A duration of
+    /// 0 prevents them from being cached.
+    pub fn cache_errors(&mut self, status: ErrorStatus, duration: usize) -> &mut Self {
+        self.error_policy.set(status, duration);
         self
     }
 
+    /// Keeps serving the cached response for up to `duration` after it expired, while it is
+    /// refreshed in the background
+    pub fn stale_while_revalidate(&mut self, duration: usize) -> &mut Self {
+        self.stale_while_revalidate = duration;
+        self
+    }
+
+    /// Serves the cached response for up to `duration` after it expired if refreshing it fails
+    /// with a server error. Server errors are then never cached, so they cannot replace the
+    /// stale response.
+    pub fn stale_if_error(&mut self, duration: usize) -> &mut Self {
+        self.stale_if_error = duration;
+        self
+    }
+
+    pub(super) fn stale_window(&self) -> usize {
+        self.stale_while_revalidate.max(self.stale_if_error)
+    }
+
+    pub(super) fn error_cache_duration_for(&self, status: u16) -> usize {
+        if status >= 500 && self.stale_if_error > 0 {
+            0
+        } else {
+            self.error_policy.duration_for(status)
+        }
+    }
+
     pub fn add_header(&mut self, header: (&str, &str)) -> &mut Self {
         self.headers
             .insert(String::from(header.0), String::from(header.1));
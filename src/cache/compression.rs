@@ -0,0 +1,54 @@
+use std::borrow::Cow;
+
+use crate::config::{cache_compression_level, cache_compression_threshold};
+
+/// Marks values stored compressed. Plain values never start with it: they are either JSON or
+/// prefixed with a status code (see [crate::cache::inner_cache::CachedWithCode]).
+pub(super) const COMPRESSED_PREFIX: &'static str = "#zstd#";
+
+/// Compresses values of at least [cache_compression_threshold] bytes with zstd. The compressed
+/// bytes are stored as they are, prefixed with [COMPRESSED_PREFIX], so they are not valid UTF-8
+/// and must be read with [crate::cache::Cache::fetch_bytes].
+pub(super) fn compress(value: &str) -> Cow<'_, [u8]> {
+    compress_above(
+        value,
+        cache_compression_threshold(),
+        cache_compression_level(),
+    )
+}
+
+pub(super) fn compress_above(value: &str, threshold: usize, level: i32) -> Cow<'_, [u8]> {
+    if threshold == 0 || value.len() < threshold {
+        return Cow::Borrowed(value.as_bytes());
+    }
+    match zstd::encode_all(value.as_bytes(), level) {
+        // Values which do not compress well are kept as they are
+        Ok(compressed) if COMPRESSED_PREFIX.len() + compressed.len() < value.len() => {
+            let mut stored = COMPRESSED_PREFIX.as_bytes().to_vec();
+            stored.extend_from_slice(&compressed);
+            Cow::Owned(stored)
+        }
+        Ok(_) => Cow::Borrowed(value.as_bytes()),
+        Err(error) => {
+            log::warn!("Compressing cache value failed: {}", error);
+            Cow::Borrowed(value.as_bytes())
+        }
+    }
+}
+
+/// Reverses [compress]. Plain values are returned as they are, whether or not compression is
+/// enabled, so that compressed and plain entries can coexist.
+pub(super) fn decompress(value: Vec<u8>) -> Option<String> {
+    let decompressed = match value.strip_prefix(COMPRESSED_PREFIX.as_bytes()) {
+        Some(compressed) => zstd::decode_all(compressed).map_err(|error| error.to_string()),
+        None => Ok(value),
+    }
+    .and_then(|bytes| String::from_utf8(bytes).map_err(|error| error.to_string()));
+    match decompressed {
+        Ok(decompressed) => Some(decompressed),
+        Err(error) => {
+            log::warn!("Decompressing cache value failed: {}", error);
+            None
+        }
+    }
+}
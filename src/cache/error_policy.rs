@@ -0,0 +1,126 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+/// Status codes an [ErrorCachePolicy] entry applies to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorStatus {
+    /// A single status code, e.g. `Code(404)`
+    Code(u16),
+    /// A whole status class by its first digit, e.g. `Class(5)` for `5xx`
+    Class(u16),
+}
+
+impl ErrorStatus {
+    /// Requests timing out are reported as `504 Gateway Timeout`
+    pub const TIMEOUT: ErrorStatus = ErrorStatus::Code(504);
+
+    fn matches(&self, status: u16) -> bool {
+        match self {
+            ErrorStatus::Code(code) => *code == status,
+            ErrorStatus::Class(class) => *class == status / 100,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if value == "timeout" {
+            return Some(ErrorStatus::TIMEOUT);
+        }
+        match value.strip_suffix("xx") {
+            Some(class) => class.parse().ok().map(ErrorStatus::Class),
+            None => value.parse().ok().map(ErrorStatus::Code),
+        }
+    }
+}
+
+impl fmt::Display for ErrorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorStatus::Code(code) => write!(f, "{}", code),
+            ErrorStatus::Class(class) => write!(f, "{}xx", class),
+        }
+    }
+}
+
+/// Maps upstream error statuses to the duration (in milliseconds) their responses are cached for.
+///
+/// Status codes take precedence over status classes, so `429=0;4xx=60000` caches every client
+/// error for a minute except rate limiting responses. Errors without a matching entry, or with a
+/// duration of 0, are not cached.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorCachePolicy {
+    entries: Vec<(ErrorStatus, usize)>,
+}
+
+impl ErrorCachePolicy {
+    /// Parses a policy in the `status=duration;status=duration` format, where a status is a code
+    /// (`404`), a class (`5xx`) or `timeout`
+    pub fn parse(policy: &str) -> Self {
+        Self::try_parse(policy)
+            .unwrap_or_else(|error| panic!("Invalid ERROR_CACHE_POLICY entry: {}", error))
+    }
+
+    /// Like [ErrorCachePolicy::parse], returning the first invalid entry as the error
+    pub fn try_parse(policy: &str) -> Result<Self, String> {
+        let mut error_policy = ErrorCachePolicy::default();
+        for entry in policy.split(';').map(str::trim) {
+            if entry.is_empty() {
+                continue;
+            }
+            let (status, duration) = entry
+                .split_once('=')
+                .and_then(|(status, duration)| {
+                    Some((ErrorStatus::parse(status)?, duration.trim().parse().ok()?))
+                })
+                .ok_or_else(|| entry.to_string())?;
+            error_policy.set(status, duration);
+        }
+        Ok(error_policy)
+    }
+
+    /// Caches errors matching `status` for `duration`, replacing any previous entry for it
+    pub fn set(&mut self, status: ErrorStatus, duration: usize) -> &mut Self {
+        self.entries.retain(|(existing, _)| *existing != status);
+        self.entries.push((status, duration));
+        self
+    }
+
+    pub fn duration_for(&self, status: u16) -> usize {
+        let matching = |specific: bool| {
+            self.entries.iter().find(|(entry_status, _)| {
+                matches!(entry_status, ErrorStatus::Code(_)) == specific
+                    && entry_status.matches(status)
+            })
+        };
+        matching(true)
+            .or_else(|| matching(false))
+            .map_or(0, |(_, duration)| *duration)
+    }
+}
+
+impl FromStr for ErrorCachePolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        Self::try_parse(policy).map_err(|entry| format!("invalid entry {}", entry))
+    }
+}
+
+impl fmt::Display for ErrorCachePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(status, duration)| format!("{}={}", status, duration))
+            .collect();
+        write!(f, "{}", entries.join(";"))
+    }
+}
+
+impl Serialize for ErrorCachePolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
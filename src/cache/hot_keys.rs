@@ -0,0 +1,76 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MAX_SHARDS: usize = 16;
+
+/// Counts the reads of each cache key within fixed windows, to tell which entries are read often
+/// enough to be refreshed ahead of their expiry. At most `max_keys` keys are tracked at a time.
+///
+/// Keys are spread over several independently locked shards, so concurrent reads of different
+/// keys rarely wait on each other.
+pub(super) struct HotKeys {
+    shards: Vec<Mutex<Shard>>,
+    max_keys_per_shard: usize,
+}
+
+#[derive(Default)]
+struct Shard {
+    reads: HashMap<String, Reads>,
+    last_sweep: Option<Instant>,
+}
+
+struct Reads {
+    window_start: Instant,
+    count: usize,
+}
+
+impl HotKeys {
+    pub(super) fn new(max_keys: usize) -> Self {
+        let shard_count = max_keys.clamp(1, MAX_SHARDS);
+        HotKeys {
+            shards: (0..shard_count).map(|_| Mutex::default()).collect(),
+            max_keys_per_shard: (max_keys + shard_count - 1) / shard_count,
+        }
+    }
+
+    /// Records a read of `key`, returning whether it was read at least `threshold` times within
+    /// the current `window`
+    pub(super) fn record(&self, key: &str, threshold: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        let mut shard = self.shard_for(key).lock().expect("Hot keys lock poisoned");
+        if shard.reads.len() >= self.max_keys_per_shard && !shard.reads.contains_key(key) {
+            // Expired keys are only swept once per window, not on every read of an untracked key
+            let sweep_due = shard
+                .last_sweep
+                .map_or(true, |last_sweep| now.duration_since(last_sweep) >= window);
+            if sweep_due {
+                shard.last_sweep = Some(now);
+                shard
+                    .reads
+                    .retain(|_, key_reads| now.duration_since(key_reads.window_start) < window);
+            }
+            if shard.reads.len() >= self.max_keys_per_shard {
+                return false;
+            }
+        }
+        let key_reads = shard.reads.entry(key.to_string()).or_insert(Reads {
+            window_start: now,
+            count: 0,
+        });
+        if now.duration_since(key_reads.window_start) >= window {
+            key_reads.window_start = now;
+            key_reads.count = 0;
+        }
+        key_reads.count += 1;
+        key_reads.count >= threshold
+    }
+
+    fn shard_for(&self, key: &str) -> &Mutex<Shard> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+}
@@ -21,6 +21,15 @@ impl CachedWithCode {
         }
     }
 
+    /// Like [CachedWithCode::split], for values which may not have been stored with a code
+    pub(super) fn try_split(cached: &str) -> Option<Self> {
+        let (code, data) = cached.split_once(CachedWithCode::SEPARATOR)?;
+        Some(CachedWithCode {
+            code: code.parse().ok()?,
+            data: data.to_string(),
+        })
+    }
+
     pub(super) fn join(code: u16, data: &str) -> String {
         format!("{}{}{}", code, CachedWithCode::SEPARATOR, data)
     }
@@ -0,0 +1,87 @@
+use ethcontract_common::hash::keccak256;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::config::cache_key_max_length;
+use crate::utils::digest;
+
+lazy_static! {
+    static ref ADDRESS: Regex = Regex::new(r"\b0x[0-9a-fA-F]{40}\b").unwrap();
+}
+
+/// Normalizes a request URI used as a response cache key, so that logically identical requests
+/// share one cache entry:
+/// - addresses are EIP-55 checksummed
+/// - query parameters are sorted by name, and those set to their default value are dropped
+/// - keys longer than [cache_key_max_length] are truncated and suffixed with a hash of the key
+pub(super) fn normalize_key(key: &str, query_defaults: &[(String, String)]) -> String {
+    let key = checksum_addresses(key);
+    let key = match key.split_once('?') {
+        Some((path, query)) => {
+            let mut params: Vec<&str> = query
+                .split('&')
+                .filter(|param| !param.is_empty() && !is_default(param, query_defaults))
+                .collect();
+            // Stable, so repeated parameters keep their relative order
+            params.sort_by_key(|param| param_name(param));
+            if params.is_empty() {
+                path.to_string()
+            } else {
+                format!("{}?{}", path, params.join("&"))
+            }
+        }
+        None => key,
+    };
+    shorten(key, cache_key_max_length())
+}
+
+/// Replaces every address in `value` with its EIP-55 checksummed form
+pub(super) fn checksum_addresses(value: &str) -> String {
+    ADDRESS
+        .replace_all(value, |captures: &Captures| checksum_address(&captures[0]))
+        .into_owned()
+}
+
+fn checksum_address(address: &str) -> String {
+    let lowercase = address[2..].to_lowercase();
+    let hash = keccak256(lowercase.as_bytes());
+    let checksummed: String = lowercase
+        .chars()
+        .enumerate()
+        .map(|(index, c)| {
+            let shift = if index % 2 == 0 { 4 } else { 0 };
+            let nibble = (hash[index / 2] >> shift) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+fn param_name(param: &str) -> &str {
+    param.split('=').next().unwrap_or(param)
+}
+
+fn is_default(param: &str, query_defaults: &[(String, String)]) -> bool {
+    param.split_once('=').map_or(false, |(name, value)| {
+        query_defaults
+            .iter()
+            .any(|(default_name, default_value)| name == default_name && value == default_value)
+    })
+}
+
+// The beginning of the key is kept, so pattern based invalidations and tags still apply to it
+fn shorten(key: String, max_length: usize) -> String {
+    if key.len() <= max_length {
+        return key;
+    }
+    let hash = digest(&key);
+    let mut end = max_length.saturating_sub(hash.len() + 1);
+    while !key.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}#{}", &key[..end], hash)
+}
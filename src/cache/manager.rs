@@ -1,18 +1,33 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::cache::redis::{new_service_cache, new_service_cache_mainnet};
+use crate::cache::memory::new_in_memory_cache;
+use crate::cache::redis::new_redis_cache;
+use crate::cache::two_tier::new_two_tier_cache;
 use crate::cache::Cache;
+use crate::config::{
+    cache_backend, cache_routes, current, l1_cache_duration, l1_cache_enabled,
+    l1_cache_max_entries, redis_pool_size, redis_pool_size_for, redis_uri_for,
+};
 
 pub enum ChainCache {
     Mainnet,
+    Chain(String),
     Other,
 }
 
 impl From<&str> for ChainCache {
     fn from(id: &str) -> Self {
-        match id {
-            "1" => ChainCache::Mainnet,
-            _ => ChainCache::Other,
+        ChainCache::Chain(id.to_string())
+    }
+}
+
+impl ChainCache {
+    pub fn chain_id(&self) -> Option<&str> {
+        match self {
+            ChainCache::Mainnet => Some("1"),
+            ChainCache::Chain(chain_id) => Some(chain_id),
+            ChainCache::Other => None,
         }
     }
 }
@@ -20,17 +35,116 @@ impl From<&str> for ChainCache {
 #[rocket::async_trait]
 pub trait RedisCacheManager: Send + Sync {
     fn cache_for_chain(&self, chain_cache: ChainCache) -> Arc<dyn Cache>;
+    /// Every distinct cache backend, e.g. for flushing all of them
+    fn all_caches(&self) -> Vec<Arc<dyn Cache>>;
 }
 
 pub struct DefaultRedisCacheManager {
-    mainnet_cache: Arc<dyn Cache>,
     default_cache: Arc<dyn Cache>,
+    named_caches: Vec<Arc<dyn Cache>>,
+    chain_caches: HashMap<String, Arc<dyn Cache>>,
 }
 
 pub async fn create_cache_manager() -> DefaultRedisCacheManager {
-    DefaultRedisCacheManager {
-        mainnet_cache: Arc::new(new_service_cache_mainnet().await),
-        default_cache: Arc::new(new_service_cache().await),
+    let default_cache = create_cache(None)
+        .await
+        .expect("REDIS_URI missing in config");
+    let mut routes = vec![];
+    // Routes are checked when the configuration is read, see `Config::validate`
+    let cache_routes = parse_cache_routes(&cache_routes()).unwrap_or_else(|error| {
+        log::error!(
+            "CACHE_ROUTES: {}, serving every chain from the default cache",
+            error
+        );
+        vec![]
+    });
+    for (name, chain_ids) in cache_routes {
+        match create_cache(Some(&name)).await {
+            Some(cache) => routes.push((chain_ids, cache)),
+            None => log::error!(
+                "REDIS_URI_{} missing, serving chains {} from the default cache",
+                name.to_uppercase(),
+                chain_ids.join(",")
+            ),
+        }
+    }
+    DefaultRedisCacheManager::new(default_cache, routes)
+}
+
+// [None] if the URI of the redis backend is missing
+async fn create_cache(name: Option<&str>) -> Option<Arc<dyn Cache>> {
+    match cache_backend().as_str() {
+        "memory" => return Some(with_l1_cache(Arc::new(new_in_memory_cache()), None)),
+        "redis" => {}
+        backend => panic!("Unsupported CACHE_BACKEND: {}", backend),
+    }
+    let (uri, pool_size) = match name {
+        Some(name) => (redis_uri_for(name)?, redis_pool_size_for(name)),
+        None => (current().redis_uri.clone()?, redis_pool_size()),
+    };
+    let cache = new_redis_cache(&uri, pool_size).await;
+    Some(with_l1_cache(cache, Some(&uri)))
+}
+
+// Other instances only share the L2 layer, and hear about its invalidations, through the Redis
+// deployment at `redis_uri`
+fn with_l1_cache(cache: Arc<dyn Cache>, redis_uri: Option<&str>) -> Arc<dyn Cache> {
+    if !l1_cache_enabled() {
+        return cache;
+    }
+    let cache = new_two_tier_cache(cache, l1_cache_max_entries(), l1_cache_duration());
+    if let Some(redis_uri) = redis_uri {
+        cache.listen_for_invalidations(redis_uri);
+    }
+    Arc::new(cache)
+}
+
+/// Parses `name=chain_id,chain_id;name=chain_id` into the chain ids served by each backend
+pub(crate) fn parse_cache_routes(routes: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    routes
+        .split(';')
+        .map(str::trim)
+        .filter(|route| !route.is_empty())
+        .map(|route| {
+            let (name, chain_ids) = route
+                .split_once('=')
+                .ok_or_else(|| format!("invalid entry {}", route))?;
+            let chain_ids = chain_ids
+                .split(',')
+                .map(str::trim)
+                .filter(|chain_id| !chain_id.is_empty())
+                .map(String::from)
+                .collect();
+            Ok((name.trim().to_string(), chain_ids))
+        })
+        .collect()
+}
+
+impl DefaultRedisCacheManager {
+    pub(super) fn new(
+        default_cache: Arc<dyn Cache>,
+        routes: Vec<(Vec<String>, Arc<dyn Cache>)>,
+    ) -> Self {
+        let mut named_caches = vec![];
+        let mut chain_caches = HashMap::new();
+        for (chain_ids, cache) in routes {
+            for chain_id in chain_ids {
+                chain_caches.insert(chain_id, cache.clone());
+            }
+            named_caches.push(cache);
+        }
+        DefaultRedisCacheManager {
+            default_cache,
+            named_caches,
+            chain_caches,
+        }
+    }
+
+    fn cache_for_chain_id(&self, chain_id: &str) -> Arc<dyn Cache> {
+        self.chain_caches
+            .get(chain_id)
+            .unwrap_or(&self.default_cache)
+            .clone()
     }
 }
 
@@ -38,8 +152,15 @@ pub async fn create_cache_manager() -> DefaultRedisCacheManager {
 impl RedisCacheManager for DefaultRedisCacheManager {
     fn cache_for_chain(&self, chain_cache: ChainCache) -> Arc<dyn Cache> {
         match chain_cache {
-            ChainCache::Mainnet => self.mainnet_cache.clone(),
+            ChainCache::Mainnet => self.cache_for_chain_id("1"),
+            ChainCache::Chain(chain_id) => self.cache_for_chain_id(&chain_id),
             ChainCache::Other => self.default_cache.clone(),
         }
     }
+
+    fn all_caches(&self) -> Vec<Arc<dyn Cache>> {
+        let mut caches = vec![self.default_cache.clone()];
+        caches.extend(self.named_caches.iter().cloned());
+        caches
+    }
 }
@@ -0,0 +1,445 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::cache::Cache;
+
+enum Value {
+    // Values written through [Cache::create_tagged] may not be UTF-8
+    Plain(Vec<u8>),
+    // Copy of a value held by another cache, along with when that one expires, see
+    // [InMemoryCache::create_copy]
+    Copy {
+        value: Vec<u8>,
+        original_expires_at: Option<Instant>,
+    },
+    Hash(HashMap<String, String>),
+}
+
+struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| expires_at <= now)
+    }
+}
+
+// Orders keys by expiry, keys without a TTL last
+type ExpiryKey = (bool, Option<Instant>, String);
+
+fn expiry_key(key: &str, expires_at: Option<Instant>) -> ExpiryKey {
+    (expires_at.is_none(), expires_at, key.to_string())
+}
+
+/// Entries along with an index of their keys by expiry, so that purging expired entries and
+/// evicting those closest to expiry never scans the whole map
+#[derive(Default)]
+struct Entries {
+    values: HashMap<String, Entry>,
+    by_expiry: BTreeSet<ExpiryKey>,
+}
+
+impl Entries {
+    fn insert(&mut self, key: &str, entry: Entry) {
+        self.remove(key);
+        self.by_expiry.insert(expiry_key(key, entry.expires_at));
+        self.values.insert(key.to_string(), entry);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.values.remove(key)?;
+        self.by_expiry.remove(&expiry_key(key, entry.expires_at));
+        Some(entry)
+    }
+
+    fn set_expiry(&mut self, key: &str, expires_at: Instant) {
+        if let Some(entry) = self.values.get_mut(key) {
+            self.by_expiry.remove(&expiry_key(key, entry.expires_at));
+            self.by_expiry.insert(expiry_key(key, Some(expires_at)));
+            entry.expires_at = Some(expires_at);
+        }
+    }
+
+    fn live_entry(&mut self, key: &str) -> Option<&mut Entry> {
+        let now = Instant::now();
+        if self
+            .values
+            .get(key)
+            .map_or(false, |entry| entry.is_expired(now))
+        {
+            self.remove(key);
+        }
+        self.values.get_mut(key)
+    }
+
+    // Key of the entry closest to expiry, expired ones first
+    fn first_to_expire(&self) -> Option<&ExpiryKey> {
+        self.by_expiry.iter().next()
+    }
+
+    fn purge_expired(&mut self, now: Instant) {
+        while let Some((_, Some(expires_at), key)) = self.first_to_expire() {
+            if *expires_at > now {
+                break;
+            }
+            let key = key.to_string();
+            self.remove(&key);
+        }
+    }
+
+    fn evict_beyond(&mut self, max_entries: usize) {
+        while self.values.len() > max_entries {
+            let key = match self.first_to_expire() {
+                Some((_, _, key)) => key.to_string(),
+                None => break,
+            };
+            self.remove(&key);
+        }
+    }
+
+    fn remove_matching(&mut self, pattern: &str) {
+        let keys: Vec<String> = self
+            .values
+            .keys()
+            .filter(|key| glob_match(pattern, key))
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+}
+
+fn remaining_millis(expires_at: Instant) -> usize {
+    expires_at
+        .saturating_duration_since(Instant::now())
+        .as_millis() as usize
+}
+
+/// Process local [Cache] implementation.
+///
+/// Mirrors the subset of Redis semantics used by the gateway: plain values with a TTL in
+/// milliseconds, hashes, expiration of existing entities and glob-style pattern deletes.
+pub struct InMemoryCache {
+    entries: Mutex<Entries>,
+    max_entries: Option<usize>,
+}
+
+pub fn new_in_memory_cache() -> InMemoryCache {
+    InMemoryCache {
+        entries: Mutex::new(Entries::default()),
+        max_entries: None,
+    }
+}
+
+/// Creates an [InMemoryCache] holding at most `max_entries` keys. When the limit is exceeded the
+/// entries closest to expiring are evicted first.
+pub fn new_bounded_in_memory_cache(max_entries: usize) -> InMemoryCache {
+    InMemoryCache {
+        entries: Mutex::new(Entries::default()),
+        max_entries: Some(max_entries),
+    }
+}
+
+impl InMemoryCache {
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().expect("In memory cache lock poisoned")
+    }
+
+    // Expired entries are also removed lazily on access, this catches the keys never read again
+    fn on_write(&self, entries: &mut Entries) {
+        entries.purge_expired(Instant::now());
+        if let Some(max_entries) = self.max_entries {
+            entries.evict_beyond(max_entries);
+        }
+    }
+
+    pub(super) fn remove_matching(&self, pattern: &str) {
+        self.entries().remove_matching(pattern);
+    }
+
+    fn create_value(&self, id: &str, value: Value, timeout: usize) {
+        let mut entries = self.entries();
+        entries.insert(
+            id,
+            Entry {
+                value,
+                expires_at: Some(Instant::now() + Duration::from_millis(timeout as u64)),
+            },
+        );
+        self.on_write(&mut entries);
+    }
+
+    /// Keeps a copy of a value held by another cache for `timeout`, reporting the remaining
+    /// `original_ttl` of that value (none if it never expires) as its TTL rather than its own
+    pub(super) fn create_copy(
+        &self,
+        id: &str,
+        dest: &[u8],
+        timeout: usize,
+        original_ttl: Option<usize>,
+    ) {
+        let now = Instant::now();
+        let value = Value::Copy {
+            value: dest.to_vec(),
+            original_expires_at: original_ttl
+                .map(|original_ttl| now + Duration::from_millis(original_ttl as u64)),
+        };
+        self.create_value(id, value, timeout)
+    }
+}
+
+#[rocket::async_trait]
+impl Cache for InMemoryCache {
+    async fn fetch(&self, id: &str) -> Option<String> {
+        String::from_utf8(self.fetch_bytes(id).await?).ok()
+    }
+
+    async fn fetch_bytes(&self, id: &str) -> Option<Vec<u8>> {
+        let mut entries = self.entries();
+        match entries.live_entry(id) {
+            Some(Entry {
+                value: Value::Plain(value),
+                ..
+            })
+            | Some(Entry {
+                value: Value::Copy { value, .. },
+                ..
+            }) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    async fn fetch_with_ttl(&self, id: &str) -> Option<(Vec<u8>, Option<usize>)> {
+        let mut entries = self.entries();
+        match entries.live_entry(id) {
+            Some(Entry {
+                value: Value::Plain(value),
+                expires_at,
+            }) => Some((value.clone(), expires_at.map(remaining_millis))),
+            Some(Entry {
+                value:
+                    Value::Copy {
+                        value,
+                        original_expires_at,
+                    },
+                ..
+            }) => Some((value.clone(), original_expires_at.map(remaining_millis))),
+            _ => None,
+        }
+    }
+
+    async fn create(&self, id: &str, dest: &str, timeout: usize) {
+        self.create_value(id, Value::Plain(dest.as_bytes().to_vec()), timeout)
+    }
+
+    async fn insert_in_hash(&self, hash: &str, id: &str, dest: &str) {
+        let mut entries = self.entries();
+        match entries.live_entry(hash) {
+            Some(Entry {
+                value: Value::Hash(values),
+                ..
+            }) => {
+                values.insert(id.to_string(), dest.to_string());
+            }
+            _ => {
+                let mut values = HashMap::new();
+                values.insert(id.to_string(), dest.to_string());
+                entries.insert(
+                    hash,
+                    Entry {
+                        value: Value::Hash(values),
+                        expires_at: None,
+                    },
+                );
+            }
+        }
+        self.on_write(&mut entries);
+    }
+
+    async fn get_from_hash(&self, hash: &str, id: &str) -> Option<String> {
+        let mut entries = self.entries();
+        match entries.live_entry(hash) {
+            Some(Entry {
+                value: Value::Hash(values),
+                ..
+            }) => values.get(id).cloned(),
+            _ => None,
+        }
+    }
+
+    async fn has_key(&self, id: &str) -> bool {
+        let mut entries = self.entries();
+        entries.live_entry(id).is_some()
+    }
+
+    async fn ttl(&self, id: &str) -> Option<usize> {
+        let mut entries = self.entries();
+        entries.live_entry(id)?.expires_at.map(remaining_millis)
+    }
+
+    async fn expire_entity(&self, id: &str, timeout: usize) {
+        let mut entries = self.entries();
+        if entries.live_entry(id).is_some() {
+            entries.set_expiry(id, Instant::now() + Duration::from_millis(timeout as u64));
+        }
+    }
+
+    async fn try_lock(&self, id: &str, token: &str, timeout: usize) -> bool {
+        let mut entries = self.entries();
+        if entries.live_entry(id).is_some() {
+            return false;
+        }
+        entries.insert(
+            id,
+            Entry {
+                value: Value::Plain(token.as_bytes().to_vec()),
+                expires_at: Some(Instant::now() + Duration::from_millis(timeout as u64)),
+            },
+        );
+        self.on_write(&mut entries);
+        true
+    }
+
+    async fn unlock(&self, id: &str, token: &str) {
+        let mut entries = self.entries();
+        if let Some(Entry {
+            value: Value::Plain(value),
+            ..
+        }) = entries.live_entry(id)
+        {
+            if value.as_slice() == token.as_bytes() {
+                entries.remove(id);
+            }
+        }
+    }
+
+    // Scanning the process local map is cheap, so tags are not tracked
+    async fn create_tagged(&self, id: &str, dest: &[u8], _tags: &[String], timeout: usize) {
+        self.create_value(id, Value::Plain(dest.to_vec()), timeout)
+    }
+
+    async fn invalidate_tagged(&self, _tag: &str, pattern: &str) {
+        self.invalidate_pattern(pattern).await
+    }
+
+    async fn tagged_keys(&self, _tag: &str, pattern: &str, limit: usize) -> Vec<String> {
+        self.keys(pattern, limit).await
+    }
+
+    async fn keys(&self, pattern: &str, limit: usize) -> Vec<String> {
+        let now = Instant::now();
+        self.entries()
+            .values
+            .iter()
+            .filter(|(key, entry)| !entry.is_expired(now) && glob_match(pattern, key))
+            .map(|(key, _)| key.to_string())
+            .take(limit)
+            .collect()
+    }
+
+    async fn invalidate_pattern(&self, pattern: &str) {
+        self.remove_matching(pattern);
+    }
+
+    async fn invalidate(&self, id: &str) {
+        self.entries().remove(id);
+    }
+
+    // Process local, so there is nobody to publish to
+    async fn publish(&self, _channel: &str, _message: &str) {}
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn info(&self) -> Option<String> {
+        let mut entries = self.entries();
+        entries.purge_expired(Instant::now());
+        Some(format!(
+            "# Memory\r\nbackend:in_memory\r\nkeys:{}\r\n",
+            entries.values.len()
+        ))
+    }
+}
+
+/// Matches `value` against a Redis style glob `pattern`.
+///
+/// Supports `*`, `?`, character classes (`[abc]`, `[a-z]`, `[^a]`) and `\` escapes.
+pub(super) fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    // Position of the last `*` in the pattern and of the value char it is currently covering
+    let mut star: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, v));
+            p += 1;
+            continue;
+        }
+        if let Some(next) = (p < pattern.len())
+            .then(|| match_single(&pattern, p, value[v]))
+            .flatten()
+        {
+            p = next;
+            v += 1;
+            continue;
+        }
+        match star {
+            Some((star_p, star_v)) => {
+                p = star_p + 1;
+                v = star_v + 1;
+                star = Some((star_p, star_v + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Returns the index of the next pattern token if the token at `p` matches `c`
+fn match_single(pattern: &[char], p: usize, c: char) -> Option<usize> {
+    match pattern[p] {
+        '?' => Some(p + 1),
+        '\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then(|| p + 2),
+        '[' => match_class(pattern, p, c),
+        literal => (literal == c).then(|| p + 1),
+    }
+}
+
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let mut i = start + 1;
+    let negate = pattern.get(i) == Some(&'^');
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    while i < pattern.len() && pattern[i] != ']' {
+        if pattern[i] == '\\' && i + 1 < pattern.len() {
+            matched |= pattern[i + 1] == c;
+            i += 2;
+        } else if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            let (low, high) = if pattern[i] <= pattern[i + 2] {
+                (pattern[i], pattern[i + 2])
+            } else {
+                (pattern[i + 2], pattern[i])
+            };
+            matched |= low <= c && c <= high;
+            i += 3;
+        } else {
+            matched |= pattern[i] == c;
+            i += 1;
+        }
+    }
+    if i >= pattern.len() {
+        // Unterminated class: treat the opening bracket as a literal
+        return ('[' == c).then(|| start + 1);
+    }
+    (matched != negate).then(|| i + 1)
+}
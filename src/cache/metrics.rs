@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+
+use crate::cache::memory::glob_match;
+use crate::config::base_config_service_uri;
+
+const FAMILY_PATTERNS: &[(&str, &str)] = &[
+    ("balances", "*/balances*"),
+    ("collectibles", "*/collectibles*"),
+    ("contracts", "*/contracts/*"),
+    ("tokens", "*/tokens*"),
+    ("transactions", "*transaction*"),
+    ("transactions", "*transfer*"),
+];
+
+/// Family of a cache key, used for grouping cache metrics
+pub(super) fn key_family(key: &str) -> &'static str {
+    if key.contains(&base_config_service_uri()) || is_chain_info(key) {
+        return "chains";
+    }
+    for (family, pattern) in FAMILY_PATTERNS {
+        if glob_match(pattern, key) {
+            return family;
+        }
+    }
+    if is_safe_info(key) {
+        return "safe_info";
+    }
+    "other"
+}
+
+// e.g. `/v1/chains` or `/v1/chains/4`
+fn is_chain_info(key: &str) -> bool {
+    let path = key.split('?').next().unwrap_or(key);
+    path.trim_end_matches('/')
+        .rsplit_once("/chains")
+        .map_or(false, |(_, rest)| {
+            rest.is_empty()
+                || rest
+                    .strip_prefix('/')
+                    .map_or(false, |id| id.chars().all(|c| c.is_ascii_digit()))
+        })
+}
+
+// e.g. `/v1/safes/0x1230.../` or `/v1/chains/4/safes/0x1230...`
+fn is_safe_info(key: &str) -> bool {
+    key.trim_end_matches('/')
+        .rsplit_once("/safes/")
+        .map_or(false, |(_, rest)| {
+            rest.starts_with("0x") && !rest.contains('/')
+        })
+}
+
+pub(super) fn chain_id_from_key(key: &str) -> Option<&str> {
+    let (_, rest) = key.split_once("/chains/")?;
+    let chain_id = rest.split(|c| c == '/' || c == '?').next()?;
+    (!chain_id.is_empty() && chain_id.chars().all(|c| c.is_ascii_digit())).then(|| chain_id)
+}
+
+/// Chain of a cache key, used for grouping cache metrics. Chain ids come from request paths, so
+/// only those in `known_chain_ids` (see [Config::metrics_chain_ids]) get their own label.
+///
+/// [Config::metrics_chain_ids]: crate::config::Config::metrics_chain_ids
+pub(super) fn chain_label<'a>(
+    chain_id: Option<&'a str>,
+    known_chain_ids: &HashSet<String>,
+) -> &'a str {
+    match chain_id {
+        None => "-1",
+        Some(chain_id) if known_chain_ids.contains(chain_id) => chain_id,
+        Some(_) => "other",
+    }
+}
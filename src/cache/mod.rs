@@ -2,9 +2,20 @@ use mockall::automock;
 
 mod cache_op_executors;
 pub mod cache_operations;
+mod compression;
+pub mod error_policy;
+mod hot_keys;
 mod inner_cache;
+mod keys;
 pub mod manager;
+pub mod memory;
+mod metrics;
 pub mod redis;
+pub mod redis_cluster;
+mod single_flight;
+mod soft_expiry;
+mod tags;
+pub mod two_tier;
 
 #[cfg(test)]
 mod tests;
@@ -12,17 +23,43 @@ mod tests;
 const CACHE_REQS_PREFIX: &'static str = "c_reqs";
 const CACHE_RESP_PREFIX: &'static str = "c_resp";
 const CACHE_REQS_RESP_PREFIX: &'static str = "c_re";
+pub(crate) const CACHE_LOCK_PREFIX: &'static str = "c_lock";
+const CACHE_TAG_PREFIX: &'static str = "c_tag";
+const CACHE_INVALIDATION_CHANNEL: &'static str = "c_invalidations";
 
 #[automock]
 #[rocket::async_trait]
 pub trait Cache: Send + Sync {
     async fn fetch(&self, id: &str) -> Option<String>;
+    /// [Cache::fetch] of values which may not be UTF-8, e.g. those written by [Cache::create_tagged]
+    async fn fetch_bytes(&self, id: &str) -> Option<Vec<u8>>;
+    /// [Cache::fetch_bytes] along with the remaining time to live of the value, see [Cache::ttl]
+    async fn fetch_with_ttl(&self, id: &str) -> Option<(Vec<u8>, Option<usize>)>;
     async fn create(&self, id: &str, dest: &str, timeout: usize);
     async fn insert_in_hash(&self, hash: &str, id: &str, dest: &str);
     async fn get_from_hash(&self, hash: &str, id: &str) -> Option<String>;
     async fn has_key(&self, id: &str) -> bool;
+    /// Remaining time to live of `id` in milliseconds, [None] if missing or without expiration
+    async fn ttl(&self, id: &str) -> Option<usize>;
     async fn expire_entity(&self, id: &str, timeout: usize);
+    /// Sets `id` to the owner `token` only if it does not exist yet, returning whether it was set
+    async fn try_lock(&self, id: &str, token: &str, timeout: usize) -> bool;
+    /// Deletes `id` only if it is still held with `token`, see [Cache::try_lock]
+    async fn unlock(&self, id: &str, token: &str);
+    /// [Cache::create] of a value which may not be UTF-8 (e.g. compressed), registering `id` under
+    /// each of `tags` for as long as it lives
+    async fn create_tagged(&self, id: &str, dest: &[u8], tags: &[String], timeout: usize);
+    /// Deletes the keys registered under `tag` that match `pattern`
+    async fn invalidate_tagged(&self, tag: &str, pattern: &str);
+    /// Up to `limit` of the keys [Cache::invalidate_tagged] would delete, without deleting them
+    async fn tagged_keys(&self, tag: &str, pattern: &str, limit: usize) -> Vec<String>;
+    /// Up to `limit` keys matching `pattern`, in no particular order
+    async fn keys(&self, pattern: &str, limit: usize) -> Vec<String>;
     async fn invalidate_pattern(&self, pattern: &str);
     async fn invalidate(&self, id: &str);
+    /// Publishes `message` to the subscribers of `channel`, if the backend supports it
+    async fn publish(&self, channel: &str, message: &str);
+    /// Whether the backend can currently be reached. Unavailable caches behave as if empty.
+    async fn is_available(&self) -> bool;
     async fn info(&self) -> Option<String>;
 }
@@ -1,95 +1,599 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
 use bb8_redis::bb8::{self, Pool, PooledConnection};
-use bb8_redis::redis::{cmd, AsyncCommands, AsyncIter, Cmd, Pipeline, ToRedisArgs};
+use bb8_redis::redis::{
+    cmd, AsyncCommands, AsyncIter, Client, Cmd, Pipeline, RedisError, RedisResult, ToRedisArgs,
+};
 use bb8_redis::RedisConnectionManager;
+use chrono::Utc;
+use rocket::futures::future::BoxFuture;
+use rocket::futures::{FutureExt, StreamExt};
+use rocket::tokio::time::{self, sleep};
 
-use crate::cache::Cache;
-use crate::config::{redis_scan_count, redis_uri, redis_uri_mainnet};
+use crate::cache::memory::glob_match;
+use crate::cache::redis_cluster::new_cluster_cache;
+use crate::cache::{Cache, CACHE_TAG_PREFIX};
+use crate::config::{redis_connection_timeout, redis_reconnect_interval, redis_scan_count};
 
 type RedisPool = Pool<RedisConnectionManager>;
-type RedisConnection<'a> = PooledConnection<'a, RedisConnectionManager>;
+type RedisConnection = PooledConnection<'static, RedisConnectionManager>;
+// Tries to re-establish the connection to Redis, returning whether it succeeded
+pub(super) type Reconnect = Arc<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// Redis deployment, selected through the scheme of the redis URI:
+/// - `redis://[user:password@]host:port[/db]`: a single instance
+/// - `redis+sentinel://[user:password@]sentinel:port[,sentinel:port...]/master_name[/db]`: the
+///   master monitored by the given sentinels, credentials and db apply to the master
+/// - `redis+cluster://[user:password@]node:port[,node:port...]`: a Redis Cluster
+#[derive(Debug, PartialEq)]
+pub(crate) enum RedisTopology {
+    Standalone(String),
+    Sentinel(SentinelConfig),
+    Cluster(Vec<String>),
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) struct SentinelConfig {
+    pub(super) sentinels: Vec<String>,
+    pub(super) master_name: String,
+    pub(super) credentials: String,
+    pub(super) db: String,
+}
+
+impl RedisTopology {
+    /// Fails on sentinel URIs without a master name
+    pub(crate) fn parse(uri: &str) -> Result<Self, String> {
+        if let Some(rest) = uri.strip_prefix("redis+cluster://") {
+            let (credentials, hosts) = split_credentials(rest);
+            return Ok(RedisTopology::Cluster(
+                hosts
+                    .trim_end_matches('/')
+                    .split(',')
+                    .map(|host| format!("redis://{}{}", credentials, host))
+                    .collect(),
+            ));
+        }
+        if let Some(rest) = uri.strip_prefix("redis+sentinel://") {
+            let (credentials, rest) = split_credentials(rest);
+            let (hosts, path) = rest.split_once('/').unwrap_or((rest, ""));
+            let (master_name, db) = path.split_once('/').unwrap_or((path, ""));
+            if master_name.is_empty() {
+                return Err(String::from(
+                    "sentinel redis URIs must include the master name",
+                ));
+            }
+            return Ok(RedisTopology::Sentinel(SentinelConfig {
+                sentinels: hosts
+                    .split(',')
+                    .map(|host| format!("redis://{}", host))
+                    .collect(),
+                master_name: master_name.to_string(),
+                credentials: credentials.to_string(),
+                db: db.to_string(),
+            }));
+        }
+        Ok(RedisTopology::Standalone(uri.to_string()))
+    }
+}
+
+// Splits `user:password@hosts` into `user:password@` and `hosts`
+fn split_credentials(value: &str) -> (&str, &str) {
+    match value.rfind('@') {
+        Some(index) => value.split_at(index + 1),
+        None => ("", value),
+    }
+}
 
-async fn create_pool(for_mainnet: bool) -> RedisPool {
-    let target = if for_mainnet {
-        redis_uri_mainnet()
-    } else {
-        redis_uri()
+impl SentinelConfig {
+    async fn resolve_master_uri(&self) -> Option<String> {
+        for sentinel in &self.sentinels {
+            let query = async {
+                let mut conn = Client::open(sentinel.as_str())?
+                    .get_async_connection()
+                    .await?;
+                cmd("SENTINEL")
+                    .arg("get-master-addr-by-name")
+                    .arg(&self.master_name)
+                    .query_async::<_, Option<(String, u16)>>(&mut conn)
+                    .await
+            };
+            match time::timeout(Duration::from_millis(redis_connection_timeout()), query).await {
+                Ok(Ok(Some((host, port)))) => {
+                    log::info!("Redis master {} is {}:{}", self.master_name, host, port);
+                    return Some(format!(
+                        "redis://{}{}:{}/{}",
+                        self.credentials, host, port, self.db
+                    ));
+                }
+                Ok(Ok(None)) => log::warn!("{} does not monitor {}", sentinel, self.master_name),
+                Ok(Err(error)) => log::warn!("Querying {} failed: {}", sentinel, error),
+                Err(_) => log::warn!("Querying {} timed out", sentinel),
+            }
+        }
+        None
+    }
+}
+
+pub async fn new_redis_cache(uri: &str, pool_size: u32) -> Arc<dyn Cache> {
+    // Redis URIs are checked when the configuration is read, see `Config::validate`
+    let topology = RedisTopology::parse(uri).expect("Invalid redis connection info");
+    match topology {
+        RedisTopology::Standalone(uri) => Arc::new(ServiceCache::new(create_pool(&uri, pool_size))),
+        RedisTopology::Sentinel(sentinel) => {
+            Arc::new(ServiceCache::with_sentinel(sentinel, pool_size).await)
+        }
+        RedisTopology::Cluster(nodes) => Arc::new(new_cluster_cache(nodes, pool_size)),
+    }
+}
+
+/// Subscribes to `channel` of the Redis deployment at `uri` in the background, calling
+/// `on_message` with the payload of every message. Subscribes again whenever the connection drops.
+pub(super) fn spawn_subscriber(
+    uri: &str,
+    channel: &str,
+    on_message: impl Fn(String) + Send + Sync + 'static,
+) {
+    let topology = match RedisTopology::parse(uri) {
+        Ok(topology) => topology,
+        Err(error) => {
+            log::error!("Not subscribing to {}: {}", channel, error);
+            return;
+        }
     };
-    let manager = RedisConnectionManager::new(target)
-        .expect("Establishing connection with redis instance failed");
+    let channel = channel.to_string();
+    rocket::tokio::spawn(async move {
+        let mut attempt = 0;
+        loop {
+            match subscriber_uri(&topology, attempt).await {
+                Some(uri) => match subscribe(&uri, &channel, &on_message).await {
+                    Ok(()) => log::warn!("Subscription to {} ended", channel),
+                    Err(error) => log::warn!("Subscribing to {} failed: {}", channel, error),
+                },
+                None => log::warn!("No redis instance to subscribe to {}", channel),
+            }
+            attempt += 1;
+            sleep(Duration::from_millis(redis_reconnect_interval())).await;
+        }
+    });
+}
+
+async fn subscriber_uri(topology: &RedisTopology, attempt: usize) -> Option<String> {
+    match topology {
+        RedisTopology::Standalone(uri) => Some(uri.clone()),
+        RedisTopology::Sentinel(sentinel) => sentinel.resolve_master_uri().await,
+        // Messages are forwarded to every node, so any of them will do
+        RedisTopology::Cluster(nodes) => nodes.get(attempt % nodes.len()).cloned(),
+    }
+}
+
+async fn subscribe(uri: &str, channel: &str, on_message: &impl Fn(String)) -> RedisResult<()> {
+    let mut pubsub = Client::open(uri)?
+        .get_async_connection()
+        .await?
+        .into_pubsub();
+    pubsub.subscribe(channel).await?;
+    log::info!("Subscribed to {}", channel);
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        match message.get_payload::<String>() {
+            Ok(payload) => on_message(payload),
+            Err(error) => log::warn!("Invalid message on {}: {}", channel, error),
+        }
+    }
+    Ok(())
+}
+
+fn create_pool(uri: &str, pool_size: u32) -> RedisPool {
+    let manager = RedisConnectionManager::new(uri).expect("Invalid redis connection info");
+    // Connections are established lazily, so that the service can start while Redis is down
     bb8::Pool::builder()
-        .max_size(15) // default is 10
-        .build(manager) // we can technically also set a connection await timeout if necessary
-        .await
-        .expect("Redis connection pool initialization failed")
+        .max_size(pool_size)
+        .connection_timeout(Duration::from_millis(redis_connection_timeout()))
+        .build_unchecked(manager)
 }
 
-pub struct ServiceCache(RedisPool);
+/// Tracks whether a Redis deployment can be reached.
+///
+/// Redis failures never surface to the callers: a failed read is a cache miss and a failed write
+/// is skipped. After a connection failure the deployment is marked as unavailable, so that every
+/// operation passes through without touching Redis, until a background task manages to reconnect.
+pub(super) struct Availability {
+    available: Arc<AtomicBool>,
+    errors: AtomicUsize,
+    reconnect: Reconnect,
+}
 
-pub async fn new_service_cache() -> impl Cache {
-    ServiceCache(create_pool(false).await)
+impl Availability {
+    pub(super) fn new(reconnect: Reconnect) -> Self {
+        Availability {
+            available: Arc::new(AtomicBool::new(true)),
+            errors: AtomicUsize::new(0),
+            reconnect,
+        }
+    }
+
+    pub(super) fn is_available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn check<T>(&self, operation: &str, result: RedisResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                let is_connection_error = is_connection_error(&error);
+                self.on_error(operation, error, is_connection_error);
+                None
+            }
+        }
+    }
+
+    pub(super) fn on_error(&self, operation: &str, error: impl Display, is_connection_error: bool) {
+        let errors = self.errors.fetch_add(1, Ordering::Relaxed) + 1;
+        log::error!(
+            "Redis {} failed ({} errors so far): {}",
+            operation,
+            errors,
+            error
+        );
+        if is_connection_error && self.available.swap(false, Ordering::Relaxed) {
+            log::warn!("Redis unavailable, bypassing the cache until it reconnects");
+            self.spawn_reconnect();
+        }
+    }
+
+    fn spawn_reconnect(&self) {
+        let available = self.available.clone();
+        let reconnect = self.reconnect.clone();
+        rocket::tokio::spawn(async move {
+            loop {
+                sleep(Duration::from_millis(redis_reconnect_interval())).await;
+                if reconnect().await {
+                    available.store(true, Ordering::Relaxed);
+                    log::info!("Redis connection restored");
+                    break;
+                }
+            }
+        });
+    }
+
+    pub(super) fn info(&self) -> String {
+        format!(
+            "# Gateway\r\navailable:{}\r\nerrors:{}\r\n",
+            self.available.load(Ordering::Relaxed),
+            self.errors.load(Ordering::Relaxed)
+        )
+    }
 }
 
-pub async fn new_service_cache_mainnet() -> impl Cache {
-    ServiceCache(create_pool(true).await)
+fn is_connection_error(error: &RedisError) -> bool {
+    error.is_io_error()
+        || error.is_connection_dropped()
+        || error.is_timeout()
+        // Writes to a master demoted by a failover
+        || error.code() == Some("READONLY")
+}
+
+/// [Cache] backed by a single Redis instance, either fixed or resolved through Redis Sentinel.
+pub struct ServiceCache {
+    // Replaced when Sentinel promotes a new master, empty while no master could be resolved
+    pool: Arc<RwLock<Option<RedisPool>>>,
+    availability: Availability,
 }
 
 impl ServiceCache {
-    async fn conn(&self) -> RedisConnection<'_> {
-        self.0.get().await.unwrap()
+    pub(super) fn new(pool: RedisPool) -> Self {
+        let pool = Arc::new(RwLock::new(Some(pool)));
+        let reconnect_pool = pool.clone();
+        let reconnect: Reconnect = Arc::new(move || {
+            let pool = current_pool(&reconnect_pool);
+            // Checked out connections are validated with a PING
+            async move {
+                match pool {
+                    Some(pool) => pool.get_owned().await.is_ok(),
+                    None => false,
+                }
+            }
+            .boxed()
+        });
+        ServiceCache {
+            pool,
+            availability: Availability::new(reconnect),
+        }
     }
+
+    async fn with_sentinel(sentinel: SentinelConfig, pool_size: u32) -> Self {
+        let sentinel = Arc::new(sentinel);
+        let master_uri = sentinel.resolve_master_uri().await;
+        let pool = Arc::new(RwLock::new(
+            master_uri
+                .as_deref()
+                .map(|master_uri| create_pool(master_uri, pool_size)),
+        ));
+
+        let reconnect_pool = pool.clone();
+        let reconnect: Reconnect = Arc::new(move || {
+            let (sentinel, pool) = (sentinel.clone(), reconnect_pool.clone());
+            async move {
+                let master_uri = match sentinel.resolve_master_uri().await {
+                    Some(master_uri) => master_uri,
+                    None => return false,
+                };
+                let master_pool = create_pool(&master_uri, pool_size);
+                if master_pool.get_owned().await.is_err() {
+                    return false;
+                }
+                *pool.write().expect("Redis pool lock poisoned") = Some(master_pool);
+                true
+            }
+            .boxed()
+        });
+
+        let cache = ServiceCache {
+            pool,
+            availability: Availability::new(reconnect),
+        };
+        if master_uri.is_none() {
+            cache
+                .availability
+                .on_error("master resolution", "no sentinel answered", true);
+        }
+        cache
+    }
+
+    async fn conn(&self) -> Option<RedisConnection> {
+        if !self.availability.is_available() {
+            return None;
+        }
+        match current_pool(&self.pool)?.get_owned().await {
+            Ok(conn) => Some(conn),
+            Err(error) => {
+                self.availability
+                    .on_error("connection checkout", error, true);
+                None
+            }
+        }
+    }
+
+    fn check<T>(&self, operation: &str, result: RedisResult<T>) -> Option<T> {
+        self.availability.check(operation, result)
+    }
+}
+
+fn current_pool(pool: &RwLock<Option<RedisPool>>) -> Option<RedisPool> {
+    pool.read().expect("Redis pool lock poisoned").clone()
 }
 
 #[rocket::async_trait]
 impl Cache for ServiceCache {
     async fn fetch(&self, id: &str) -> Option<String> {
-        match self.conn().await.get(id).await {
-            Ok(value) => Some(value),
-            _ => None,
-        }
+        let mut conn = self.conn().await?;
+        let result: RedisResult<Option<String>> = conn.get(id).await;
+        self.check("GET", result).flatten()
+    }
+
+    async fn fetch_bytes(&self, id: &str) -> Option<Vec<u8>> {
+        let mut conn = self.conn().await?;
+        let result: RedisResult<Option<Vec<u8>>> = conn.get(id).await;
+        self.check("GET", result).flatten()
+    }
+
+    async fn fetch_with_ttl(&self, id: &str) -> Option<(Vec<u8>, Option<usize>)> {
+        let mut conn = self.conn().await?;
+        let result: RedisResult<(Option<Vec<u8>>, i64)> = Pipeline::new()
+            .get(id)
+            .pttl(id)
+            .query_async(&mut *conn)
+            .await;
+        let (value, ttl) = self.check("GET", result)?;
+        Some((value?, remaining_ttl(ttl)))
     }
 
     async fn create(&self, id: &str, dest: &str, timeout: usize) {
-        let _: () = self.conn().await.pset_ex(id, dest, timeout).await.unwrap();
+        let Some(mut conn) = self.conn().await else { return };
+        let result: RedisResult<()> = conn.pset_ex(id, dest, timeout).await;
+        self.check("PSETEX", result);
     }
 
     async fn insert_in_hash(&self, hash: &str, id: &str, dest: &str) {
-        let _: () = self.conn().await.hset(hash, id, dest).await.unwrap();
+        let Some(mut conn) = self.conn().await else { return };
+        let result: RedisResult<()> = conn.hset(hash, id, dest).await;
+        self.check("HSET", result);
     }
 
     async fn get_from_hash(&self, hash: &str, id: &str) -> Option<String> {
-        self.conn().await.hget(hash, id).await.ok()
+        let mut conn = self.conn().await?;
+        let result: RedisResult<Option<String>> = conn.hget(hash, id).await;
+        self.check("HGET", result).flatten()
     }
 
     async fn has_key(&self, id: &str) -> bool {
-        let result: Option<usize> = self.conn().await.exists(id).await.ok();
-        result.map(|it| it != 0).unwrap_or(false)
+        let Some(mut conn) = self.conn().await else { return false };
+        let result: RedisResult<usize> = conn.exists(id).await;
+        self.check("EXISTS", result).map_or(false, |it| it != 0)
+    }
+
+    async fn ttl(&self, id: &str) -> Option<usize> {
+        let mut conn = self.conn().await?;
+        let result: RedisResult<i64> = conn.pttl(id).await;
+        self.check("PTTL", result).and_then(remaining_ttl)
     }
 
     async fn expire_entity(&self, id: &str, timeout: usize) {
-        let _: () = self.conn().await.pexpire(id, timeout).await.unwrap();
+        let Some(mut conn) = self.conn().await else { return };
+        let result: RedisResult<()> = conn.pexpire(id, timeout).await;
+        self.check("PEXPIRE", result);
+    }
+
+    // Fails open: if Redis cannot be reached the caller proceeds as the lock owner
+    async fn try_lock(&self, id: &str, token: &str, timeout: usize) -> bool {
+        let Some(mut conn) = self.conn().await else { return true };
+        let result: RedisResult<Option<String>> = cmd("SET")
+            .arg(id)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(timeout)
+            .query_async(&mut *conn)
+            .await;
+        self.check("SET NX", result).map_or(true, |it| it.is_some())
+    }
+
+    async fn unlock(&self, id: &str, token: &str) {
+        let Some(mut conn) = self.conn().await else { return };
+        let result: RedisResult<i64> = unlock_cmd(id, token).query_async(&mut *conn).await;
+        self.check("EVAL", result);
+    }
+
+    async fn create_tagged(&self, id: &str, dest: &[u8], tags: &[String], timeout: usize) {
+        let Some(mut conn) = self.conn().await else { return };
+        let mut pipeline = Pipeline::new();
+        pipeline.pset_ex(id, dest, timeout).ignore();
+        for tag_cmd in tag_cmds(id, tags, timeout) {
+            pipeline.add_command(tag_cmd).ignore();
+        }
+        let result: RedisResult<()> = pipeline.query_async(&mut *conn).await;
+        self.check("PSETEX tagged", result);
+    }
+
+    async fn invalidate_tagged(&self, tag: &str, pattern: &str) {
+        let Some(mut conn) = self.conn().await else { return };
+        let tag_key = tag_key(tag);
+        let result: RedisResult<Vec<String>> = conn
+            .zrangebyscore(&tag_key, Utc::now().timestamp_millis(), "+inf")
+            .await;
+        let Some(members) = self.check("ZRANGEBYSCORE", result) else { return };
+        let keys: Vec<String> = members
+            .into_iter()
+            .filter(|key| glob_match(pattern, key))
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+        let mut pipeline = Pipeline::new();
+        pipeline.del(&keys).ignore().zrem(&tag_key, &keys).ignore();
+        let result: RedisResult<()> = pipeline.query_async(&mut *conn).await;
+        self.check("pipeline delete", result);
+    }
+
+    async fn tagged_keys(&self, tag: &str, pattern: &str, limit: usize) -> Vec<String> {
+        let Some(mut conn) = self.conn().await else { return vec![] };
+        let result: RedisResult<Vec<String>> = conn
+            .zrangebyscore(tag_key(tag), Utc::now().timestamp_millis(), "+inf")
+            .await;
+        let Some(members) = self.check("ZRANGEBYSCORE", result) else { return vec![] };
+        members
+            .into_iter()
+            .filter(|key| glob_match(pattern, key))
+            .take(limit)
+            .collect()
+    }
+
+    async fn keys(&self, pattern: &str, limit: usize) -> Vec<String> {
+        let Some(mut conn) = self.conn().await else { return vec![] };
+        let keys_cmd = scan_match_count_cmd(pattern, redis_scan_count());
+        let result: RedisResult<AsyncIter<String>> = keys_cmd.iter_async(&mut *conn).await;
+        let Some(mut keys) = self.check("SCAN", result) else { return vec![] };
+        let mut matching = vec![];
+        while matching.len() < limit {
+            match keys.next_item().await {
+                Some(key) => matching.push(key),
+                None => break,
+            }
+        }
+        matching
     }
 
     async fn invalidate_pattern(&self, pattern: &str) {
-        let mut con = self.conn().await;
+        let Some(mut conn) = self.conn().await else { return };
         let keys_cmd = scan_match_count_cmd(pattern, redis_scan_count());
-        let mut keys = keys_cmd.iter_async(&mut *con).await.unwrap();
-        pipeline_delete(&mut keys)
-            .await
-            .query_async::<_, ()>(&mut *con)
-            .await
-            .expect("Pipeline delete error");
+        let result: RedisResult<AsyncIter<String>> = keys_cmd.iter_async(&mut *conn).await;
+        let Some(mut keys) = self.check("SCAN", result) else { return };
+        let pipeline = pipeline_delete(&mut keys).await;
+        drop(keys);
+        let result: RedisResult<()> = pipeline.query_async(&mut *conn).await;
+        self.check("pipeline delete", result);
     }
 
     async fn invalidate(&self, id: &str) {
-        let _: () = self.conn().await.del(id).await.unwrap();
+        let Some(mut conn) = self.conn().await else { return };
+        let result: RedisResult<()> = conn.del(id).await;
+        self.check("DEL", result);
+    }
+
+    async fn publish(&self, channel: &str, message: &str) {
+        let Some(mut conn) = self.conn().await else { return };
+        let result: RedisResult<()> = conn.publish(channel, message).await;
+        self.check("PUBLISH", result);
+    }
+
+    async fn is_available(&self) -> bool {
+        self.availability.is_available()
     }
 
     async fn info(&self) -> Option<String> {
-        let mut conn = self.conn().await;
-        cmd("INFO").query_async(&mut *conn).await.ok()
+        let redis_info: Option<String> = match self.conn().await {
+            Some(mut conn) => cmd("INFO").query_async(&mut *conn).await.ok(),
+            None => None,
+        };
+        Some(redis_info.unwrap_or_default() + &self.availability.info())
     }
 }
 
+// Deletes a lock only if it still holds the token of its owner, so that an owner whose lock expired
+// never releases the lock of the next one
+const UNLOCK_SCRIPT: &str = r#"if redis.call("GET", KEYS[1]) == ARGV[1] then return redis.call("DEL", KEYS[1]) else return 0 end"#;
+
+pub(super) fn unlock_cmd(id: &str, token: &str) -> Cmd {
+    let mut unlock = cmd("EVAL");
+    unlock.arg(UNLOCK_SCRIPT).arg(1).arg(id).arg(token);
+    unlock
+}
+
+// Negative values signal a missing key (-2) or a key without expiration (-1)
+pub(super) fn remaining_ttl(pttl: i64) -> Option<usize> {
+    (pttl >= 0).then(|| pttl as usize)
+}
+
+pub(super) fn tag_key(tag: &str) -> String {
+    format!("{}_{}", CACHE_TAG_PREFIX, tag)
+}
+
+// Tag sets are sorted sets of keys scored by their expiry (milliseconds since the epoch), so that
+// the keys which expired are pruned on every write. Tag sets themselves live as long as the
+// longest lived key registered under them.
+const TAG_SCRIPT: &str = r#"
+redis.call("ZADD", KEYS[1], ARGV[1], ARGV[2])
+redis.call("ZREMRANGEBYSCORE", KEYS[1], "-inf", "(" .. ARGV[3])
+if redis.call("PTTL", KEYS[1]) < tonumber(ARGV[4]) then
+    redis.call("PEXPIRE", KEYS[1], ARGV[4])
+end
+return 0
+"#;
+
+// Registers `id`, expiring in `timeout` milliseconds, under each of `tags`. Every command only
+// touches its tag set, so they can be sent to different cluster nodes.
+pub(super) fn tag_cmds(id: &str, tags: &[String], timeout: usize) -> Vec<Cmd> {
+    let now = Utc::now().timestamp_millis();
+    let expires_at = now + timeout as i64;
+    tags.iter()
+        .map(|tag| {
+            let mut tag_cmd = cmd("EVAL");
+            tag_cmd
+                .arg(TAG_SCRIPT)
+                .arg(1)
+                .arg(tag_key(tag))
+                .arg(expires_at)
+                .arg(id)
+                .arg(now)
+                .arg(timeout);
+            tag_cmd
+        })
+        .collect()
+}
+
 async fn pipeline_delete(keys: &mut AsyncIter<'_, String>) -> Pipeline {
     let mut pipeline = Pipeline::new();
     while let Some(key) = keys.next_item().await {
@@ -98,7 +602,7 @@ async fn pipeline_delete(keys: &mut AsyncIter<'_, String>) -> Pipeline {
     pipeline
 }
 
-fn scan_match_count_cmd<P: ToRedisArgs, C: ToRedisArgs>(pattern: P, count: C) -> Cmd {
+pub(super) fn scan_match_count_cmd<P: ToRedisArgs, C: ToRedisArgs>(pattern: P, count: C) -> Cmd {
     let mut cmd = cmd("SCAN");
     cmd.cursor_arg(0)
         .arg("MATCH")
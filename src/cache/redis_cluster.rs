@@ -0,0 +1,334 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use r2d2::Pool;
+use redis::cluster::{cluster_pipe, ClusterClient, ClusterConnection};
+use redis::{
+    cmd, Client, Commands, ConnectionAddr, ConnectionInfo, IntoConnectionInfo, RedisConnectionInfo,
+    RedisResult,
+};
+use rocket::futures::FutureExt;
+use rocket::tokio::task::spawn_blocking;
+
+use crate::cache::memory::glob_match;
+use crate::cache::redis::{
+    remaining_ttl, scan_match_count_cmd, tag_cmds, tag_key, unlock_cmd, Availability, Reconnect,
+};
+use crate::cache::Cache;
+use crate::config::{redis_connection_timeout, redis_scan_count};
+
+type ClusterPool = Pool<ClusterClient>;
+
+/// [Cache] backed by a Redis Cluster.
+///
+/// Commands are routed to the node serving the slot of their key, pattern deletes scan every
+/// master. The cluster client is blocking, so commands run on the blocking thread pool.
+pub struct ClusterCache {
+    pool: ClusterPool,
+    // Credentials and db used for connecting to individual nodes
+    node_info: RedisConnectionInfo,
+    availability: Availability,
+}
+
+pub(super) fn new_cluster_cache(nodes: Vec<String>, pool_size: u32) -> ClusterCache {
+    let node_info = nodes
+        .first()
+        .expect("Redis cluster URIs must list at least one node")
+        .as_str()
+        .into_connection_info()
+        .expect("Invalid redis cluster connection info")
+        .redis;
+    let client = ClusterClient::open(nodes).expect("Invalid redis cluster connection info");
+    // Connections are established lazily, so that the service can start while Redis is down
+    let pool = Pool::builder()
+        .max_size(pool_size)
+        .min_idle(Some(0))
+        .connection_timeout(Duration::from_millis(redis_connection_timeout()))
+        .build_unchecked(client);
+
+    let reconnect_pool = pool.clone();
+    let reconnect: Reconnect = Arc::new(move || {
+        let pool = reconnect_pool.clone();
+        // Checked out connections are validated with a PING
+        async move {
+            spawn_blocking(move || pool.get().is_ok())
+                .await
+                .unwrap_or(false)
+        }
+        .boxed()
+    });
+    ClusterCache {
+        pool,
+        node_info,
+        availability: Availability::new(reconnect),
+    }
+}
+
+impl ClusterCache {
+    async fn run<T, F>(&self, operation: &str, command: F) -> Option<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut ClusterConnection) -> RedisResult<T> + Send + 'static,
+    {
+        if !self.availability.is_available() {
+            return None;
+        }
+        let pool = self.pool.clone();
+        match spawn_blocking(move || pool.get().map(|mut conn| command(&mut conn))).await {
+            Ok(Ok(result)) => self.availability.check(operation, result),
+            Ok(Err(error)) => {
+                self.availability
+                    .on_error("connection checkout", error, true);
+                None
+            }
+            Err(error) => {
+                self.availability.on_error(operation, error, false);
+                None
+            }
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Cache for ClusterCache {
+    async fn fetch(&self, id: &str) -> Option<String> {
+        let id = id.to_string();
+        self.run("GET", move |conn| conn.get::<_, Option<String>>(&id))
+            .await
+            .flatten()
+    }
+
+    async fn fetch_bytes(&self, id: &str) -> Option<Vec<u8>> {
+        let id = id.to_string();
+        self.run("GET", move |conn| conn.get::<_, Option<Vec<u8>>>(&id))
+            .await
+            .flatten()
+    }
+
+    async fn fetch_with_ttl(&self, id: &str) -> Option<(Vec<u8>, Option<usize>)> {
+        let id = id.to_string();
+        let (value, ttl) = self
+            .run("GET", move |conn| {
+                cluster_pipe()
+                    .get(&id)
+                    .pttl(&id)
+                    .query::<(Option<Vec<u8>>, i64)>(conn)
+            })
+            .await?;
+        Some((value?, remaining_ttl(ttl)))
+    }
+
+    async fn create(&self, id: &str, dest: &str, timeout: usize) {
+        let (id, dest) = (id.to_string(), dest.to_string());
+        self.run("PSETEX", move |conn| {
+            conn.pset_ex::<_, _, ()>(&id, &dest, timeout)
+        })
+        .await;
+    }
+
+    async fn insert_in_hash(&self, hash: &str, id: &str, dest: &str) {
+        let (hash, id, dest) = (hash.to_string(), id.to_string(), dest.to_string());
+        self.run("HSET", move |conn| {
+            conn.hset::<_, _, _, ()>(&hash, &id, &dest)
+        })
+        .await;
+    }
+
+    async fn get_from_hash(&self, hash: &str, id: &str) -> Option<String> {
+        let (hash, id) = (hash.to_string(), id.to_string());
+        self.run("HGET", move |conn| {
+            conn.hget::<_, _, Option<String>>(&hash, &id)
+        })
+        .await
+        .flatten()
+    }
+
+    async fn has_key(&self, id: &str) -> bool {
+        let id = id.to_string();
+        self.run("EXISTS", move |conn| conn.exists::<_, usize>(&id))
+            .await
+            .map_or(false, |it| it != 0)
+    }
+
+    async fn ttl(&self, id: &str) -> Option<usize> {
+        let id = id.to_string();
+        self.run("PTTL", move |conn| conn.pttl::<_, i64>(&id))
+            .await
+            .and_then(remaining_ttl)
+    }
+
+    async fn expire_entity(&self, id: &str, timeout: usize) {
+        let id = id.to_string();
+        self.run("PEXPIRE", move |conn| conn.pexpire::<_, ()>(&id, timeout))
+            .await;
+    }
+
+    // Fails open: if Redis cannot be reached the caller proceeds as the lock owner
+    async fn try_lock(&self, id: &str, token: &str, timeout: usize) -> bool {
+        let id = id.to_string();
+        let token = token.to_string();
+        self.run("SET NX", move |conn| {
+            cmd("SET")
+                .arg(&id)
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(timeout)
+                .query::<Option<String>>(conn)
+        })
+        .await
+        .map_or(true, |it| it.is_some())
+    }
+
+    async fn unlock(&self, id: &str, token: &str) {
+        let unlock = unlock_cmd(id, token);
+        self.run("EVAL", move |conn| unlock.query::<i64>(conn))
+            .await;
+    }
+
+    async fn create_tagged(&self, id: &str, dest: &[u8], tags: &[String], timeout: usize) {
+        let mut pipeline = cluster_pipe();
+        pipeline.pset_ex(id, dest, timeout).ignore();
+        for tag_cmd in tag_cmds(id, tags, timeout) {
+            pipeline.add_command(tag_cmd).ignore();
+        }
+        self.run("PSETEX tagged", move |conn| pipeline.query::<()>(conn))
+            .await;
+    }
+
+    async fn invalidate_tagged(&self, tag: &str, pattern: &str) {
+        let (tag_key, pattern) = (tag_key(tag), pattern.to_string());
+        self.run("tag delete", move |conn| {
+            let members: Vec<String> =
+                conn.zrangebyscore(&tag_key, Utc::now().timestamp_millis(), "+inf")?;
+            let keys: Vec<&String> = members
+                .iter()
+                .filter(|key| glob_match(&pattern, key))
+                .collect();
+            if keys.is_empty() {
+                return Ok(());
+            }
+            // Keys live in different slots, so they are deleted one by one, batched per node
+            let mut pipeline = cluster_pipe();
+            for key in &keys {
+                pipeline.del(*key).ignore();
+            }
+            pipeline.zrem(&tag_key, keys).ignore();
+            pipeline.query::<()>(conn)
+        })
+        .await;
+    }
+
+    async fn tagged_keys(&self, tag: &str, pattern: &str, limit: usize) -> Vec<String> {
+        let (tag_key, pattern) = (tag_key(tag), pattern.to_string());
+        self.run("ZRANGEBYSCORE", move |conn| {
+            let members: Vec<String> =
+                conn.zrangebyscore(&tag_key, Utc::now().timestamp_millis(), "+inf")?;
+            Ok(members
+                .into_iter()
+                .filter(|key| glob_match(&pattern, key))
+                .take(limit)
+                .collect())
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    async fn invalidate_pattern(&self, pattern: &str) {
+        let pattern = pattern.to_string();
+        let node_info = self.node_info.clone();
+        self.run("pattern delete", move |conn| {
+            let keys = scan_masters(conn, node_info, &pattern, usize::MAX)?;
+            // Keys live in different slots, so they are deleted one by one, batched per node
+            let mut pipeline = cluster_pipe();
+            for key in &keys {
+                pipeline.del(key).ignore();
+            }
+            pipeline.query::<()>(conn)
+        })
+        .await;
+    }
+
+    async fn keys(&self, pattern: &str, limit: usize) -> Vec<String> {
+        let pattern = pattern.to_string();
+        let node_info = self.node_info.clone();
+        self.run("SCAN", move |conn| {
+            scan_masters(conn, node_info, &pattern, limit)
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    async fn invalidate(&self, id: &str) {
+        let id = id.to_string();
+        self.run("DEL", move |conn| conn.del::<_, ()>(&id)).await;
+    }
+
+    // Messages published on any node are forwarded to the subscribers of every node
+    async fn publish(&self, channel: &str, message: &str) {
+        let (channel, message) = (channel.to_string(), message.to_string());
+        self.run("PUBLISH", move |conn| {
+            conn.publish::<_, _, ()>(&channel, &message)
+        })
+        .await;
+    }
+
+    async fn is_available(&self) -> bool {
+        self.availability.is_available()
+    }
+
+    async fn info(&self) -> Option<String> {
+        let redis_info = self
+            .run("INFO", |conn| cmd("INFO").query::<String>(conn))
+            .await;
+        Some(redis_info.unwrap_or_default() + &self.availability.info())
+    }
+}
+
+// Keys are spread across the masters, each of which has to be scanned on its own
+fn scan_masters(
+    conn: &mut ClusterConnection,
+    node_info: RedisConnectionInfo,
+    pattern: &str,
+    limit: usize,
+) -> RedisResult<Vec<String>> {
+    let nodes: String = cmd("CLUSTER").arg("NODES").query(conn)?;
+    let connection_timeout = Duration::from_millis(redis_connection_timeout());
+    let mut keys: Vec<String> = vec![];
+    for (host, port) in master_addresses(&nodes) {
+        let mut node = Client::open(ConnectionInfo {
+            addr: ConnectionAddr::Tcp(host, port),
+            redis: node_info.clone(),
+        })?
+        .get_connection_with_timeout(connection_timeout)?;
+        let remaining = limit - keys.len();
+        keys.extend(
+            scan_match_count_cmd(pattern, redis_scan_count())
+                .iter::<String>(&mut node)?
+                .take(remaining),
+        );
+        if keys.len() >= limit {
+            break;
+        }
+    }
+    Ok(keys)
+}
+
+/// Addresses of the healthy masters listed in the output of `CLUSTER NODES`, with one node per
+/// line: `<id> <ip:port@cport> <flags> ...`
+pub(super) fn master_addresses(nodes: &str) -> Vec<(String, u16)> {
+    nodes
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let address = fields.nth(1)?;
+            let flags: Vec<&str> = fields.next()?.split(',').collect();
+            if !flags.contains(&"master") || flags.iter().any(|flag| flag.starts_with("fail")) {
+                return None;
+            }
+            let (host, port) = address.split('@').next()?.rsplit_once(':')?;
+            Some((host.to_string(), port.parse().ok()?))
+        })
+        .collect()
+}
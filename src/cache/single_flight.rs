@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use rocket::tokio::sync::broadcast;
+
+use crate::utils::errors::ApiResult;
+
+/// Deduplicates concurrent executions of the same operation within this process.
+///
+/// The first caller for a key (the leader) runs the operation, every caller arriving while it is
+/// in flight waits for the leader and receives a copy of its result. If the leader is dropped
+/// before finishing, one of the waiting callers becomes the new leader.
+pub(super) struct SingleFlight {
+    in_flight: Mutex<HashMap<String, broadcast::Sender<ApiResult<String>>>>,
+}
+
+enum Role {
+    Leader(broadcast::Sender<ApiResult<String>>),
+    Follower(broadcast::Receiver<ApiResult<String>>),
+}
+
+// Removes the flight when the leader finishes, or when its future is dropped before finishing
+struct FlightGuard<'a> {
+    single_flight: &'a SingleFlight,
+    key: &'a str,
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        self.single_flight
+            .in_flight
+            .lock()
+            .expect("Single flight lock poisoned")
+            .remove(self.key);
+    }
+}
+
+impl SingleFlight {
+    pub(super) fn new() -> Self {
+        SingleFlight {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(super) async fn execute<F, Fut>(&self, key: &str, operation: F) -> ApiResult<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ApiResult<String>>,
+    {
+        let mut operation = Some(operation);
+        loop {
+            match self.join(key) {
+                Role::Follower(mut receiver) => {
+                    if let Ok(result) = receiver.recv().await {
+                        return result;
+                    }
+                    // The leader went away without a result (e.g. its client disconnected). The
+                    // first of its followers to join again takes over, the others follow it.
+                }
+                Role::Leader(sender) => {
+                    let guard = FlightGuard {
+                        single_flight: self,
+                        key,
+                    };
+                    // Callers only become leaders once, as leaders always return
+                    let operation = operation.take().expect("Single flight leader ran twice");
+                    let result = operation().await;
+                    drop(guard);
+                    // Sending only fails if nobody is waiting, which is fine
+                    let _ = sender.send(result.clone());
+                    return result;
+                }
+            }
+        }
+    }
+
+    fn join(&self, key: &str) -> Role {
+        let mut in_flight = self.in_flight.lock().expect("Single flight lock poisoned");
+        match in_flight.get(key) {
+            Some(sender) => Role::Follower(sender.subscribe()),
+            None => {
+                let (sender, _) = broadcast::channel(1);
+                in_flight.insert(key.to_string(), sender.clone());
+                Role::Leader(sender)
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::Utc;
+
+/// Marks values stored along with the time until which they are fresh. Like
+/// [crate::cache::compression::COMPRESSED_PREFIX], plain values never start with it.
+pub(super) const FRESH_UNTIL_PREFIX: &'static str = "#fresh#";
+
+/// Cached value which may outlive its cache duration, to be served stale within a window after it
+///
+/// The end of the cache duration is stored in the value itself, so that telling whether it is
+/// stale takes no extra round trip to the cache. It is compared against the wall clock of each
+/// gateway instance, so skewed clocks shift it by their skew.
+#[derive(Debug, PartialEq)]
+pub(super) struct SoftExpiring {
+    pub(super) value: String,
+    // Milliseconds since the epoch, [None] for values stored without it
+    fresh_until: Option<i64>,
+}
+
+impl SoftExpiring {
+    /// Stores `value` as fresh for `fresh_for` milliseconds from now
+    pub(super) fn wrap(value: &str, fresh_for: usize) -> String {
+        let fresh_until = Utc::now().timestamp_millis() + fresh_for as i64;
+        format!("{}{}#{}", FRESH_UNTIL_PREFIX, fresh_until, value)
+    }
+
+    /// Reverses [SoftExpiring::wrap]. Other values, e.g. those written before it was introduced,
+    /// are fresh for as long as they are cached.
+    pub(super) fn parse(stored: String) -> Self {
+        let parsed = stored
+            .strip_prefix(FRESH_UNTIL_PREFIX)
+            .and_then(|wrapped| wrapped.split_once('#'))
+            .and_then(|(fresh_until, value)| Some((fresh_until.parse().ok()?, value)));
+        match parsed {
+            Some((fresh_until, value)) => SoftExpiring {
+                value: value.to_string(),
+                fresh_until: Some(fresh_until),
+            },
+            None => SoftExpiring {
+                value: stored,
+                fresh_until: None,
+            },
+        }
+    }
+
+    pub(super) fn is_stale(&self) -> bool {
+        self.fresh_for() == Some(0)
+    }
+
+    /// Whether the value turned stale less than `stale_if_error` milliseconds ago, so that it can
+    /// still be served when refreshing it fails
+    pub(super) fn within_stale_if_error(&self, stale_if_error: usize) -> bool {
+        match self.fresh_until {
+            Some(fresh_until) => {
+                let stale_for = Utc::now().timestamp_millis() - fresh_until;
+                0 <= stale_for && (stale_for as usize) < stale_if_error
+            }
+            None => false,
+        }
+    }
+
+    /// Milliseconds left until the value turns stale, [None] if it never does
+    pub(super) fn fresh_for(&self) -> Option<usize> {
+        let remaining = self.fresh_until? - Utc::now().timestamp_millis();
+        Some(remaining.max(0) as usize)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::cache::memory::glob_match;
+use crate::config::base_config_service_uri;
+
+// Entity types derived from the cache key, mirroring the patterns used by [InvalidationPattern]
+//
+// [InvalidationPattern]: crate::cache::cache_operations::InvalidationPattern
+const ENTITY_PATTERNS: &[(&str, &str)] = &[
+    ("balances", "*/balances*"),
+    ("collectibles", "*/collectibles*"),
+    ("contracts", "*contract*"),
+    ("transactions", "*transactions/*"),
+    ("transfers", "*transfer*"),
+];
+
+/// Tags a cache key is registered under: the addresses and hashes (e.g. safe addresses and
+/// safeTxHashes) it contains and the entity types it holds.
+pub(super) fn tags_for_key(key: &str) -> Vec<String> {
+    let mut tags: Vec<String> = identifiers(key).filter_map(identifier_tag).collect();
+    for (entity, pattern) in ENTITY_PATTERNS {
+        if glob_match(pattern, key) {
+            tags.push(entity_tag(entity));
+        }
+    }
+    if key.contains(&base_config_service_uri()) {
+        tags.push(entity_tag("chains"));
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Tag for an address (20 bytes) or hash (32 bytes) in `0x` hex notation, case insensitive.
+pub(super) fn identifier_tag(value: &str) -> Option<String> {
+    let hex = value.strip_prefix("0x")?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        40 => Some(format!("address:{}", value.to_lowercase())),
+        64 => Some(format!("hash:{}", value.to_lowercase())),
+        _ => None,
+    }
+}
+
+pub(super) fn entity_tag(entity: &str) -> String {
+    format!("entity:{}", entity)
+}
+
+fn identifiers(key: &str) -> impl Iterator<Item = &str> {
+    key.match_indices("0x").map(move |(start, _)| {
+        let end = key[start + 2..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .map_or(key.len(), |offset| start + 2 + offset);
+        &key[start..end]
+    })
+}
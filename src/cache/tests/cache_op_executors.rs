@@ -1,13 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::cache::cache_operations::RequestCached;
-use crate::cache::manager::ChainCache;
+use crate::cache::cache_op_executors::response_cache_key;
+use crate::cache::cache_operations::{
+    CacheResponse, InvalidationPattern, InvalidationScope, RequestCached,
+};
+use crate::cache::error_policy::ErrorStatus;
+use crate::cache::inner_cache::CachedWithCode;
+use crate::cache::manager::{ChainCache, DefaultRedisCacheManager};
+use crate::cache::memory::{glob_match, new_in_memory_cache};
+use crate::cache::soft_expiry::SoftExpiring;
+use crate::cache::Cache;
+use crate::config::{cache_schema_version, refresh_ahead_hits};
 use crate::utils::context::RequestContext;
-use crate::utils::errors::{ApiError, ErrorDetails};
-use crate::utils::http_client::{HttpClient, MockHttpClient, Response};
+use crate::utils::errors::{ApiError, ApiResult, ErrorDetails};
+use crate::utils::http_client::{HttpClient, MockHttpClient, Request, Response};
 use crate::{create_cache_manager, RedisCacheManager};
+use rocket::futures::future::join_all;
+use rocket::response::content;
+use rocket::tokio::task::yield_now;
+use rocket::tokio::time::{sleep, timeout};
 use serde_json::json;
 
+// Upstream answering every GET after a delay, so that concurrent callers overlap
+#[derive(Default)]
+struct SlowUpstream {
+    gets: AtomicUsize,
+}
+
+#[rocket::async_trait]
+impl HttpClient for SlowUpstream {
+    async fn get(&self, _request: Request) -> ApiResult<Response> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        sleep(Duration::from_millis(50)).await;
+        Ok(Response {
+            body: String::from("body"),
+            status_code: 200,
+        })
+    }
+
+    async fn post(&self, _request: Request) -> ApiResult<Response> {
+        unimplemented!()
+    }
+
+    async fn delete(&self, _request: Request) -> ApiResult<Response> {
+        unimplemented!()
+    }
+}
+
 #[rocket::async_test]
 async fn error_from_backend_deserialization() {
     let request_uri = "some.url";
@@ -100,3 +141,336 @@ async fn error_from_backend_deserialization_unknown_json_struct() {
 
     assert_eq!(expected, actual);
 }
+
+#[rocket::async_test]
+async fn request_cached_serves_stale_response_on_server_error() {
+    let mut mock_http_client = MockHttpClient::new();
+    mock_http_client.expect_get().times(1).returning(move |_| {
+        Ok(Response {
+            body: String::from("cached body"),
+            status_code: 200,
+        })
+    });
+    mock_http_client.expect_get().times(1).returning(move |_| {
+        Err(ApiError::from_http_response(&Response {
+            body: String::from("Service unavailable"),
+            status_code: 503,
+        }))
+    });
+    let client = Arc::new(mock_http_client) as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+
+    let mut request = RequestCached::new(String::from("some.url"), &client, &cache);
+    request.cache_duration(1).stale_if_error(60 * 1000);
+
+    assert_eq!(request.execute().await, Ok(String::from("cached body")));
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(request.execute().await, Ok(String::from("cached body")));
+}
+
+#[rocket::async_test]
+async fn request_cached_revalidates_stale_response_in_background() {
+    let mut mock_http_client = MockHttpClient::new();
+    mock_http_client.expect_get().times(1).returning(move |_| {
+        Ok(Response {
+            body: String::from("first body"),
+            status_code: 200,
+        })
+    });
+    mock_http_client.expect_get().times(1).returning(move |_| {
+        Ok(Response {
+            body: String::from("second body"),
+            status_code: 200,
+        })
+    });
+    let client = Arc::new(mock_http_client) as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+
+    let mut request = RequestCached::new(String::from("some.url"), &client, &cache);
+    request
+        .cache_duration(100)
+        .stale_while_revalidate(60 * 1000);
+
+    assert_eq!(request.execute().await, Ok(String::from("first body")));
+    sleep(Duration::from_millis(150)).await;
+    // Stale: served from cache while it is refreshed in the background
+    assert_eq!(request.execute().await, Ok(String::from("first body")));
+    let refreshed = timeout(Duration::from_secs(5), async {
+        while request.execute().await != Ok(String::from("second body")) {
+            yield_now().await;
+        }
+    })
+    .await;
+    assert!(refreshed.is_ok());
+}
+
+#[rocket::async_test]
+async fn request_cached_propagates_server_errors_without_stale_if_error() {
+    let mut mock_http_client = MockHttpClient::new();
+    mock_http_client.expect_get().times(1).returning(move |_| {
+        Err(ApiError::from_http_response(&Response {
+            body: String::from("Service unavailable"),
+            status_code: 503,
+        }))
+    });
+    let client = Arc::new(mock_http_client) as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    // Stale, e.g. written while a stale window was configured
+    cache
+        .create(
+            "c_reqs_stale.url",
+            &SoftExpiring::wrap(&CachedWithCode::join(200, "cached body"), 0),
+            60 * 1000,
+        )
+        .await;
+
+    let request = RequestCached::new(String::from("stale.url"), &client, &cache);
+
+    assert_eq!(request.execute().await.unwrap_err().status, 503);
+}
+
+// Response counting the times it was generated
+async fn generated_response(
+    context: &RequestContext,
+    generated: &Arc<AtomicUsize>,
+) -> ApiResult<content::RawJson<String>> {
+    let background = generated.clone();
+    CacheResponse::new(context, ChainCache::Other)
+        .duration(100)
+        .stale_while_revalidate(60 * 1000, move || {
+            let generated = background.clone();
+            async move { Ok(generated.fetch_add(1, Ordering::SeqCst)) }
+        })
+        .resp_generator(|| async { Ok(generated.fetch_add(1, Ordering::SeqCst)) })
+        .execute()
+        .await
+}
+
+#[rocket::async_test]
+async fn cache_response_revalidates_stale_response_in_background() {
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    let cache_manager =
+        Arc::new(DefaultRedisCacheManager::new(cache, vec![])) as Arc<dyn RedisCacheManager>;
+    let client = Arc::new(MockHttpClient::new()) as Arc<dyn HttpClient>;
+    let context = RequestContext::setup_for_test(
+        String::from("/v1/generated"),
+        String::from("host"),
+        &client,
+        &cache_manager,
+    )
+    .await;
+    let generated = Arc::new(AtomicUsize::new(0));
+
+    assert_eq!(
+        generated_response(&context, &generated).await.unwrap().0,
+        "0"
+    );
+    sleep(Duration::from_millis(150)).await;
+    // Stale: served from cache while it is regenerated in the background
+    assert_eq!(
+        generated_response(&context, &generated).await.unwrap().0,
+        "0"
+    );
+    let regenerated = timeout(Duration::from_secs(5), async {
+        while generated.load(Ordering::SeqCst) < 2 {
+            yield_now().await;
+        }
+    })
+    .await;
+    assert!(regenerated.is_ok());
+    assert_eq!(
+        generated_response(&context, &generated).await.unwrap().0,
+        "1"
+    );
+}
+
+// Cache-Control of a public response served in a fresh request context
+async fn public_response_cache_control(
+    cache_manager: &Arc<dyn RedisCacheManager>,
+) -> Option<String> {
+    let client = Arc::new(MockHttpClient::new()) as Arc<dyn HttpClient>;
+    let context = RequestContext::setup_for_test(
+        String::from("/v1/public"),
+        String::from("host"),
+        &client,
+        cache_manager,
+    )
+    .await;
+    CacheResponse::new(&context, ChainCache::Other)
+        .duration(60 * 1000)
+        .public()
+        .resp_generator(|| async { Ok(0) })
+        .execute()
+        .await
+        .unwrap();
+    context.response_hints().cache_control()
+}
+
+#[rocket::async_test]
+async fn cache_response_advertises_remaining_freshness_of_public_responses() {
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    let cache_manager =
+        Arc::new(DefaultRedisCacheManager::new(cache, vec![])) as Arc<dyn RedisCacheManager>;
+
+    assert_eq!(
+        public_response_cache_control(&cache_manager).await,
+        Some(String::from("public, max-age=60"))
+    );
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(
+        public_response_cache_control(&cache_manager).await,
+        Some(String::from("public, max-age=59"))
+    );
+}
+
+#[rocket::async_test]
+async fn request_cached_does_not_normalize_upstream_urls() {
+    let upstream = Arc::new(SlowUpstream::default());
+    let client = upstream.clone() as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+
+    for url in [
+        "https://tx.service/api/v1/safes/0x1230b3d59858296a31053c1b8562ecf89a2f888b/",
+        "https://tx.service/api/v1/safes/0x1230B3d59858296A31053C1b8562Ecf89A2f888b/",
+    ] {
+        RequestCached::new(String::from(url), &client, &cache)
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(upstream.gets.load(Ordering::SeqCst), 2);
+}
+
+#[rocket::async_test]
+async fn request_cached_applies_error_cache_policy() {
+    let mut mock_http_client = MockHttpClient::new();
+    mock_http_client
+        .expect_get()
+        .times(2)
+        .returning(move |request| {
+            let status_code = if request == Request::new(String::from("not.found")) {
+                404
+            } else {
+                503
+            };
+            Err(ApiError::from_http_response(&Response {
+                body: String::from("Error"),
+                status_code,
+            }))
+        });
+    let client = Arc::new(mock_http_client) as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+
+    let mut not_found = RequestCached::new(String::from("not.found"), &client, &cache);
+    not_found
+        .error_cache_duration(0)
+        .cache_errors(ErrorStatus::Code(404), 60 * 1000);
+    let mut unavailable = RequestCached::new(String::from("unavailable"), &client, &cache);
+    unavailable.cache_errors(ErrorStatus::Class(5), 0);
+
+    // The 404 is cached, so the second call is served from the cache
+    for _ in 0..2 {
+        assert_eq!(not_found.execute().await.unwrap_err().status, 404);
+    }
+    assert_eq!(unavailable.execute().await.unwrap_err().status, 503);
+    assert!(!cache.has_key("c_reqs_unavailable").await);
+}
+
+#[rocket::async_test]
+async fn request_cached_does_not_cache_errors_answered_without_the_upstream() {
+    let mut mock_http_client = MockHttpClient::new();
+    mock_http_client.expect_get().times(2).returning(move |_| {
+        Err(ApiError::new_upstream_unavailable(String::from(
+            "Upstream circuit.open is unavailable, retry later",
+        )))
+    });
+    let client = Arc::new(mock_http_client) as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+
+    let mut request = RequestCached::new(String::from("circuit.open"), &client, &cache);
+    request.cache_errors(ErrorStatus::Class(5), 60 * 1000);
+
+    // Not cached, so the second call goes to the HTTP client again
+    for _ in 0..2 {
+        assert!(request
+            .execute()
+            .await
+            .unwrap_err()
+            .is_upstream_unavailable());
+    }
+    assert!(!cache.has_key("c_reqs_circuit.open").await);
+}
+
+#[rocket::async_test]
+async fn request_cached_coalesces_concurrent_misses() {
+    let upstream = Arc::new(SlowUpstream::default());
+    let client = upstream.clone() as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    let request = RequestCached::new(String::from("coalesced.url"), &client, &cache);
+
+    let results = join_all((0..10).map(|_| request.execute())).await;
+
+    assert_eq!(upstream.gets.load(Ordering::SeqCst), 1);
+    assert!(results
+        .into_iter()
+        .all(|result| result == Ok(String::from("body"))));
+}
+
+#[rocket::async_test]
+async fn request_cached_refreshes_hot_entries_ahead_of_expiry() {
+    let mut mock_http_client = MockHttpClient::new();
+    mock_http_client.expect_get().times(1).returning(move |_| {
+        Ok(Response {
+            body: String::from("second body"),
+            status_code: 200,
+        })
+    });
+    let client = Arc::new(mock_http_client) as Arc<dyn HttpClient>;
+    let cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    // Fresh for one more second only, well within the refresh window of a one minute duration
+    cache
+        .create(
+            "c_reqs_hot.url",
+            &SoftExpiring::wrap(&CachedWithCode::join(200, "first body"), 1000),
+            60 * 1000,
+        )
+        .await;
+
+    let mut request = RequestCached::new(String::from("hot.url"), &client, &cache);
+    request.cache_duration(60 * 1000);
+
+    // Still served from cache while it is refreshed in the background
+    for _ in 0..refresh_ahead_hits() {
+        assert_eq!(request.execute().await, Ok(String::from("first body")));
+    }
+    let refreshed = timeout(Duration::from_secs(5), async {
+        while request.execute().await != Ok(String::from("second body")) {
+            yield_now().await;
+        }
+    })
+    .await;
+    assert!(refreshed.is_ok());
+    assert!(cache.ttl("c_reqs_hot.url").await.unwrap() > 59 * 1000);
+}
+
+#[test]
+fn response_cache_key_is_versioned_and_invalidatable() {
+    let key = response_cache_key(
+        "/v1/chains/1/safes/0x1230B3d59858296A31053C1b8562Ecf89A2f888b/balances/usd",
+        &[],
+    );
+    let pattern = InvalidationPattern::Balances(
+        InvalidationScope::Responses,
+        String::from("0x1230B3d59858296A31053C1b8562Ecf89A2f888b"),
+    );
+
+    assert_eq!(
+        key,
+        format!(
+            "c_resp_{}_/v1/chains/1/safes/0x1230B3d59858296A31053C1b8562Ecf89A2f888b/balances/usd",
+            cache_schema_version()
+        )
+    );
+    assert!(glob_match(&pattern.to_pattern_string(), &key));
+}
@@ -114,3 +114,26 @@ fn invalidation_scope_responses_to_string() {
         InvalidationScope::Responses.invalidation_scope_string()
     )
 }
+
+#[test]
+fn invalidation_patterns_matching_warmed_up_data() {
+    let safe_address = "0x1230B3d59858296A31053C1b8562Ecf89A2f888b";
+
+    assert!(InvalidationPattern::Chains.matches_warmed_up_data());
+    assert!(InvalidationPattern::Tokens {
+        chain_id: String::from("1")
+    }
+    .matches_warmed_up_data());
+    assert!(
+        InvalidationPattern::Any(InvalidationScope::Both, String::from("safe-apps"))
+            .matches_warmed_up_data()
+    );
+    assert!(
+        !InvalidationPattern::Any(InvalidationScope::Both, String::from(safe_address))
+            .matches_warmed_up_data()
+    );
+    assert!(
+        !InvalidationPattern::Balances(InvalidationScope::Both, String::from(safe_address))
+            .matches_warmed_up_data()
+    );
+}
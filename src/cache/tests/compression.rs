@@ -0,0 +1,39 @@
+use std::borrow::Cow;
+
+use crate::cache::compression::{compress_above, decompress, COMPRESSED_PREFIX};
+
+#[test]
+fn compress_large_values_round_trip() {
+    let value = format!("200;{}", "{\"results\":[]}".repeat(100));
+
+    let compressed = compress_above(&value, 1024, 3);
+
+    assert!(compressed.starts_with(COMPRESSED_PREFIX.as_bytes()));
+    assert!(compressed.len() < value.len());
+    assert_eq!(decompress(compressed.into_owned()), Some(value));
+}
+
+#[test]
+fn compress_keeps_small_values_plain() {
+    let value = "200;{\"results\":[]}";
+
+    assert_eq!(
+        compress_above(value, 1024, 3),
+        Cow::Borrowed(value.as_bytes())
+    );
+    // Disabled
+    assert_eq!(compress_above(value, 0, 3), Cow::Borrowed(value.as_bytes()));
+}
+
+#[test]
+fn decompress_plain_values() {
+    assert_eq!(decompress(b"200;{}".to_vec()), Some(String::from("200;{}")));
+}
+
+#[test]
+fn decompress_corrupted_values() {
+    assert_eq!(
+        decompress(format!("{}not zstd!", COMPRESSED_PREFIX).into_bytes()),
+        None
+    );
+}
@@ -0,0 +1,44 @@
+use crate::cache::error_policy::{ErrorCachePolicy, ErrorStatus};
+
+#[test]
+fn parse_error_cache_policy() {
+    let policy = ErrorCachePolicy::parse("404=3600000; 5xx=0;timeout=1000;4xx=60000");
+
+    let mut expected = ErrorCachePolicy::default();
+    expected
+        .set(ErrorStatus::Code(404), 3600000)
+        .set(ErrorStatus::Class(5), 0)
+        .set(ErrorStatus::Code(504), 1000)
+        .set(ErrorStatus::Class(4), 60000);
+
+    assert_eq!(policy, expected);
+}
+
+#[test]
+#[should_panic(expected = "Invalid ERROR_CACHE_POLICY entry: 4x=1000")]
+fn parse_error_cache_policy_invalid_status() {
+    ErrorCachePolicy::parse("4x=1000");
+}
+
+#[test]
+fn error_cache_policy_codes_take_precedence_over_classes() {
+    let mut policy = ErrorCachePolicy::parse("4xx=60000;429=0;5xx=0;timeout=1000");
+    policy
+        .set(ErrorStatus::Class(4), 1000)
+        .set(ErrorStatus::Code(404), 5000);
+
+    assert_eq!(policy.duration_for(404), 5000);
+    assert_eq!(policy.duration_for(422), 1000);
+    assert_eq!(policy.duration_for(429), 0);
+    assert_eq!(policy.duration_for(503), 0);
+    assert_eq!(policy.duration_for(504), 1000);
+    assert_eq!(policy.duration_for(302), 0);
+}
+
+#[test]
+fn error_cache_policy_displays_in_its_configured_format() {
+    let policy: ErrorCachePolicy = "404=3600000;5xx=0;timeout=1000".parse().unwrap();
+
+    assert_eq!(policy.to_string(), "404=3600000;5xx=0;504=1000");
+    assert_eq!(policy.to_string().parse(), Ok(policy));
+}
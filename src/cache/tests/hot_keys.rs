@@ -0,0 +1,38 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use crate::cache::hot_keys::HotKeys;
+
+#[test]
+fn hot_keys_reach_threshold_within_window() {
+    let hot_keys = HotKeys::new(10);
+    let window = Duration::from_secs(60);
+
+    assert!(!hot_keys.record("key", 3, window));
+    assert!(!hot_keys.record("key", 3, window));
+    assert!(hot_keys.record("key", 3, window));
+    assert!(!hot_keys.record("other_key", 3, window));
+}
+
+#[test]
+fn hot_keys_reset_count_after_window() {
+    let hot_keys = HotKeys::new(10);
+    let window = Duration::from_millis(20);
+
+    assert!(!hot_keys.record("key", 2, window));
+    sleep(Duration::from_millis(30));
+    assert!(!hot_keys.record("key", 2, window));
+    assert!(hot_keys.record("key", 2, window));
+}
+
+#[test]
+fn hot_keys_track_bounded_number_of_keys() {
+    let hot_keys = HotKeys::new(1);
+    let window = Duration::from_millis(20);
+
+    assert!(hot_keys.record("key", 1, window));
+    // Not tracked while the window of the first key is running
+    assert!(!hot_keys.record("other_key", 1, window));
+    sleep(Duration::from_millis(30));
+    assert!(hot_keys.record("other_key", 1, window));
+}
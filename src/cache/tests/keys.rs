@@ -0,0 +1,70 @@
+use crate::cache::keys::{checksum_addresses, normalize_key};
+
+#[test]
+fn checksum_addresses_in_key() {
+    let expected = "/v1/chains/4/safes/0x1230B3d59858296A31053C1b8562Ecf89A2f888b/balances/usd";
+
+    assert_eq!(
+        checksum_addresses(
+            "/v1/chains/4/safes/0x1230b3d59858296a31053c1b8562ecf89a2f888b/balances/usd"
+        ),
+        expected
+    );
+    assert_eq!(
+        checksum_addresses(
+            "/v1/chains/4/safes/0x1230B3D59858296A31053C1B8562ECF89A2F888B/balances/usd"
+        ),
+        expected
+    );
+    assert_eq!(checksum_addresses(expected), expected);
+}
+
+#[test]
+fn checksum_addresses_ignores_hashes() {
+    let key = "/v1/chains/4/transactions/0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621";
+
+    assert_eq!(checksum_addresses(key), key);
+}
+
+#[test]
+fn normalize_key_sorts_query_parameters_and_drops_defaults() {
+    let query_defaults = vec![
+        (String::from("trusted"), String::from("false")),
+        (String::from("exclude_spam"), String::from("true")),
+    ];
+
+    assert_eq!(
+        normalize_key(
+            "/v1/chains/4/safes/0x1230b3d59858296a31053c1b8562ecf89a2f888b/balances/usd?trusted=false&exclude_spam=false",
+            &query_defaults
+        ),
+        "/v1/chains/4/safes/0x1230B3d59858296A31053C1b8562Ecf89A2f888b/balances/usd?exclude_spam=false"
+    );
+    assert_eq!(
+        normalize_key(
+            "/v1/chains?trusted=false&exclude_spam=true",
+            &query_defaults
+        ),
+        "/v1/chains"
+    );
+    assert_eq!(
+        normalize_key(
+            "https://tx.service/api/v1/safes/?offset=20&limit=20&a=2&a=1",
+            &[]
+        ),
+        "https://tx.service/api/v1/safes/?a=2&a=1&limit=20&offset=20"
+    );
+}
+
+#[test]
+fn normalize_key_shortens_long_keys() {
+    let key = format!("/v1/chains/4/safe-apps?client_url={}", "a".repeat(512));
+    let other_key = format!("/v1/chains/4/safe-apps?client_url={}b", "a".repeat(511));
+
+    let actual = normalize_key(&key, &[]);
+
+    assert!(actual.len() <= 256);
+    assert!(actual.starts_with("/v1/chains/4/safe-apps?client_url=aaa"));
+    assert_ne!(actual, normalize_key(&other_key, &[]));
+    assert_eq!(actual, normalize_key(&key, &[]));
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use crate::cache::manager::{
+    parse_cache_routes, ChainCache, DefaultRedisCacheManager, RedisCacheManager,
+};
+use crate::cache::memory::new_in_memory_cache;
+use crate::cache::Cache;
+
+#[test]
+fn parse_cache_routes_with_groups() {
+    let expected = vec![
+        (String::from("mainnet"), vec![String::from("1")]),
+        (
+            String::from("l2"),
+            vec![String::from("137"), String::from("42161")],
+        ),
+    ];
+
+    let actual = parse_cache_routes("mainnet=1; l2=137,42161;").unwrap();
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn parse_cache_routes_rejects_entries_without_chains() {
+    let actual = parse_cache_routes("mainnet=1;l2");
+
+    assert_eq!(Err(String::from("invalid entry l2")), actual);
+}
+
+#[rocket::async_test]
+async fn cache_for_chain_uses_routes() {
+    let default_cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    let mainnet_cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    let l2_cache = Arc::new(new_in_memory_cache()) as Arc<dyn Cache>;
+    let cache_manager = DefaultRedisCacheManager::new(
+        default_cache,
+        vec![
+            (vec![String::from("1")], mainnet_cache),
+            (vec![String::from("137"), String::from("42161")], l2_cache),
+        ],
+    );
+
+    cache_manager
+        .cache_for_chain(ChainCache::Mainnet)
+        .create("key", "mainnet", 60 * 1000)
+        .await;
+    cache_manager
+        .cache_for_chain(ChainCache::from("137"))
+        .create("key", "l2", 60 * 1000)
+        .await;
+    cache_manager
+        .cache_for_chain(ChainCache::Other)
+        .create("key", "default", 60 * 1000)
+        .await;
+
+    let cache_for = |chain_cache: ChainCache| cache_manager.cache_for_chain(chain_cache);
+    assert_eq!(
+        Some(String::from("mainnet")),
+        cache_for(ChainCache::from("1")).fetch("key").await
+    );
+    assert_eq!(
+        Some(String::from("l2")),
+        cache_for(ChainCache::from("42161")).fetch("key").await
+    );
+    assert_eq!(
+        Some(String::from("default")),
+        cache_for(ChainCache::from("100")).fetch("key").await
+    );
+    assert_eq!(3, cache_manager.all_caches().len());
+}
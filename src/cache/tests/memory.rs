@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use rocket::tokio::time::sleep;
+
+use crate::cache::memory::{glob_match, new_bounded_in_memory_cache, new_in_memory_cache};
+use crate::cache::Cache;
+
+#[test]
+fn glob_match_wildcards() {
+    assert!(glob_match("*", "c_reqs_some_url"));
+    assert!(glob_match(
+        "c_re*0x1230*",
+        "c_resp_/v1/chains/1/safes/0x1230/balances"
+    ));
+    assert!(glob_match(
+        "c_re*/0x1230/balances*",
+        "c_reqs_http://tx/0x1230/balances/usd"
+    ));
+    assert!(!glob_match("c_re*0x1230*", "dip_ti_1"));
+    assert!(!glob_match("c_reqs*", "c_resp_key"));
+}
+
+#[test]
+fn glob_match_single_char_and_classes() {
+    assert!(glob_match("h?llo", "hello"));
+    assert!(!glob_match("h?llo", "hllo"));
+    assert!(glob_match("h[ae]llo", "hallo"));
+    assert!(!glob_match("h[^e]llo", "hello"));
+    assert!(glob_match("h[a-c]llo", "hbllo"));
+    assert!(glob_match("h\\*llo", "h*llo"));
+    assert!(!glob_match("h\\*llo", "hello"));
+}
+
+#[rocket::async_test]
+async fn in_memory_create_and_fetch() {
+    let cache = new_in_memory_cache();
+
+    cache.create("key", "value", 60 * 1000).await;
+
+    assert_eq!(cache.fetch("key").await, Some(String::from("value")));
+    assert!(cache.has_key("key").await);
+    assert_eq!(cache.fetch("missing").await, None);
+}
+
+#[rocket::async_test]
+async fn in_memory_entries_expire() {
+    let cache = new_in_memory_cache();
+
+    cache.create("key", "value", 1).await;
+    sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(cache.fetch("key").await, None);
+    assert!(!cache.has_key("key").await);
+}
+
+#[rocket::async_test]
+async fn in_memory_hash_and_expire_entity() {
+    let cache = new_in_memory_cache();
+
+    cache.insert_in_hash("hash", "field", "value").await;
+    assert_eq!(
+        cache.get_from_hash("hash", "field").await,
+        Some(String::from("value"))
+    );
+    assert_eq!(cache.get_from_hash("hash", "other").await, None);
+    assert_eq!(cache.fetch("hash").await, None);
+
+    cache.expire_entity("hash", 1).await;
+    sleep(Duration::from_millis(10)).await;
+
+    assert!(!cache.has_key("hash").await);
+}
+
+#[rocket::async_test]
+async fn in_memory_invalidate_pattern() {
+    let cache = new_in_memory_cache();
+    cache
+        .create("c_reqs_/0x1230/balances", "1", 60 * 1000)
+        .await;
+    cache
+        .create("c_resp_/0x1230/collectibles", "2", 60 * 1000)
+        .await;
+    cache
+        .create("c_resp_/0x4560/collectibles", "3", 60 * 1000)
+        .await;
+
+    cache.invalidate_pattern("c_re*0x1230*").await;
+
+    assert!(!cache.has_key("c_reqs_/0x1230/balances").await);
+    assert!(!cache.has_key("c_resp_/0x1230/collectibles").await);
+    assert!(cache.has_key("c_resp_/0x4560/collectibles").await);
+
+    cache.invalidate("c_resp_/0x4560/collectibles").await;
+
+    assert!(!cache.has_key("c_resp_/0x4560/collectibles").await);
+}
+
+#[rocket::async_test]
+async fn bounded_in_memory_evicts_closest_to_expiry() {
+    let cache = new_bounded_in_memory_cache(2);
+
+    cache.create("short", "1", 10 * 1000).await;
+    cache.create("long", "2", 60 * 1000).await;
+    cache.create("medium", "3", 30 * 1000).await;
+
+    assert!(!cache.has_key("short").await);
+    assert!(cache.has_key("long").await);
+    assert!(cache.has_key("medium").await);
+}
+
+#[rocket::async_test]
+async fn bounded_in_memory_evicts_by_updated_expiry() {
+    let cache = new_bounded_in_memory_cache(2);
+
+    cache.create("first", "1", 60 * 1000).await;
+    cache.create("second", "2", 30 * 1000).await;
+    cache.expire_entity("first", 10 * 1000).await;
+    cache.create("third", "3", 45 * 1000).await;
+
+    assert!(!cache.has_key("first").await);
+    assert!(cache.has_key("second").await);
+    assert!(cache.has_key("third").await);
+}
+
+#[rocket::async_test]
+async fn in_memory_lock_is_only_released_by_its_owner() {
+    let cache = new_in_memory_cache();
+
+    assert!(cache.try_lock("lock", "owner", 60 * 1000).await);
+    assert!(!cache.try_lock("lock", "other", 60 * 1000).await);
+
+    cache.unlock("lock", "other").await;
+    assert!(cache.has_key("lock").await);
+    cache.unlock("lock", "owner").await;
+    assert!(!cache.has_key("lock").await);
+}
+
+#[rocket::async_test]
+async fn in_memory_keys_matching_pattern() {
+    let cache = new_in_memory_cache();
+
+    cache
+        .create("c_resp_/0x1230/balances", "1", 60 * 1000)
+        .await;
+    cache
+        .create("c_reqs_/0x1230/balances", "2", 60 * 1000)
+        .await;
+    cache
+        .create("c_resp_/0x4560/balances", "3", 60 * 1000)
+        .await;
+    cache.create("c_resp_/0x1230/expired", "4", 1).await;
+    sleep(Duration::from_millis(10)).await;
+
+    let mut keys = cache.keys("c_re*0x1230*", 10).await;
+    keys.sort();
+
+    assert_eq!(
+        keys,
+        vec!["c_reqs_/0x1230/balances", "c_resp_/0x1230/balances"]
+    );
+    assert_eq!(cache.keys("c_re*0x1230*", 1).await.len(), 1);
+}
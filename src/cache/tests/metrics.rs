@@ -0,0 +1,74 @@
+use crate::cache::metrics::{chain_id_from_key, chain_label, key_family};
+use crate::config::{with_config, Config, ConfigSource};
+
+fn config() -> Config {
+    Config::from_source(&ConfigSource::from_pairs(&[
+        ("CONFIG_SERVICE_URI", "https://config-url-example.com"),
+        ("WEBHOOK_TOKEN", "webhook_token"),
+        ("REDIS_URI", "redis://localhost:6379"),
+        ("REDIS_URI_MAINNET", "redis://localhost:6379"),
+    ]))
+    .unwrap()
+}
+
+#[rocket::async_test]
+async fn key_families() {
+    with_config(config(), async {
+        let safe = "0x1230B3d59858296A31053C1b8562Ecf89A2f888b";
+
+        assert_eq!(
+            "balances",
+            key_family(&format!("c_resp_/v1/chains/4/safes/{}/balances/usd", safe))
+        );
+        assert_eq!(
+            "transactions",
+            key_family(&format!(
+                "c_reqs_https://tx.service/api/v1/safes/{}/multisig-transactions/",
+                safe
+            ))
+        );
+        assert_eq!(
+            "safe_info",
+            key_family(&format!("c_reqs_https://tx.service/api/v1/safes/{}/", safe))
+        );
+        assert_eq!(
+            "safe_info",
+            key_family(&format!("c_resp_/v1/chains/4/safes/{}", safe))
+        );
+        assert_eq!(
+            "chains",
+            key_family("c_reqs_https://config-url-example.com/api/v1/chains/4/")
+        );
+        assert_eq!("chains", key_family("c_resp_/v1/chains/4"));
+        assert_eq!(
+            "tokens",
+            key_family(&format!(
+                "c_reqs_https://tx.service/api/v1/tokens/{}/",
+                safe
+            ))
+        );
+        assert_eq!("other", key_family("c_resp_/health"));
+    })
+    .await;
+}
+
+#[test]
+fn chain_labels_are_bounded_to_known_chains() {
+    let known_chain_ids = ["1", "137"].iter().map(|id| id.to_string()).collect();
+
+    assert_eq!("137", chain_label(Some("137"), &known_chain_ids));
+    assert_eq!("other", chain_label(Some("31337"), &known_chain_ids));
+    assert_eq!("-1", chain_label(None, &known_chain_ids));
+}
+
+#[test]
+fn chain_ids_from_keys() {
+    assert_eq!(
+        Some("137"),
+        chain_id_from_key("c_resp_/v1/chains/137/safes/0x1230")
+    );
+    assert_eq!(Some("4"), chain_id_from_key("c_resp_/v1/chains/4?limit=20"));
+    assert_eq!(None, chain_id_from_key("c_resp_/v1/chains/"));
+    assert_eq!(None, chain_id_from_key("c_resp_/v1/chains/abc/about"));
+    assert_eq!(None, chain_id_from_key("c_resp_/health"));
+}
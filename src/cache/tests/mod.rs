@@ -1,3 +1,15 @@
 mod cache_inner;
 mod cache_op_executors;
 mod cache_operations;
+mod compression;
+mod error_policy;
+mod hot_keys;
+mod keys;
+mod manager;
+mod memory;
+mod metrics;
+mod redis;
+mod single_flight;
+mod soft_expiry;
+mod tags;
+mod two_tier;
@@ -0,0 +1,97 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use rocket::futures::future::{join_all, pending};
+use rocket::tokio::task::yield_now;
+use rocket::tokio::time::timeout;
+
+use crate::cache::single_flight::SingleFlight;
+use crate::utils::errors::ApiError;
+
+#[rocket::async_test]
+async fn single_flight_coalesces_concurrent_calls() {
+    let single_flight = SingleFlight::new();
+    let calls = AtomicUsize::new(0);
+
+    let results = join_all((0..5).map(|_| {
+        single_flight.execute("key", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            // Every caller joins before the leader resumes
+            yield_now().await;
+            Ok(String::from("response"))
+        })
+    }))
+    .await;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(results
+        .into_iter()
+        .all(|result| result == Ok(String::from("response"))));
+}
+
+#[rocket::async_test]
+async fn single_flight_shares_errors() {
+    let single_flight = SingleFlight::new();
+    let calls = AtomicUsize::new(0);
+
+    let results = join_all((0..3).map(|_| {
+        single_flight.execute("key", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            yield_now().await;
+            Err(ApiError::new_from_message_with_code(
+                503,
+                String::from("unavailable"),
+            ))
+        })
+    }))
+    .await;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(results
+        .into_iter()
+        .all(|result| result.unwrap_err().status == 503));
+}
+
+#[rocket::async_test]
+async fn single_flight_does_not_coalesce_sequential_calls() {
+    let single_flight = SingleFlight::new();
+    let calls = AtomicUsize::new(0);
+
+    for _ in 0..2 {
+        single_flight
+            .execute("key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(String::from("response"))
+            })
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[rocket::async_test]
+async fn single_flight_promotes_a_follower_when_the_leader_is_dropped() {
+    let single_flight = SingleFlight::new();
+    let calls = AtomicUsize::new(0);
+
+    let leader = timeout(
+        Duration::from_millis(10),
+        single_flight.execute("key", || pending()),
+    );
+    let followers = join_all((0..3).map(|_| {
+        single_flight.execute("key", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            // The other followers join the promoted one before it resumes
+            yield_now().await;
+            Ok(String::from("follower"))
+        })
+    }));
+    let (leader, results) = rocket::futures::join!(leader, followers);
+
+    assert!(leader.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(results
+        .into_iter()
+        .all(|result| result == Ok(String::from("follower"))));
+}
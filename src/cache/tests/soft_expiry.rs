@@ -0,0 +1,44 @@
+use crate::cache::soft_expiry::{SoftExpiring, FRESH_UNTIL_PREFIX};
+
+#[test]
+fn soft_expiring_round_trip() {
+    let wrapped = SoftExpiring::wrap("200;{}", 60 * 1000);
+    assert!(wrapped.starts_with(FRESH_UNTIL_PREFIX));
+
+    let parsed = SoftExpiring::parse(wrapped);
+
+    assert_eq!(parsed.value, "200;{}");
+    assert!(!parsed.is_stale());
+    assert!(parsed.fresh_for().unwrap() > 59 * 1000);
+}
+
+#[test]
+fn soft_expiring_is_stale_after_its_duration() {
+    let parsed = SoftExpiring::parse(format!("{}1000#{{}}", FRESH_UNTIL_PREFIX));
+
+    assert_eq!(parsed.value, "{}");
+    assert!(parsed.is_stale());
+    assert_eq!(parsed.fresh_for(), Some(0));
+}
+
+#[test]
+fn soft_expiring_plain_values_are_never_stale() {
+    let parsed = SoftExpiring::parse(String::from("200;{}"));
+
+    assert_eq!(parsed.value, "200;{}");
+    assert!(!parsed.is_stale());
+    assert_eq!(parsed.fresh_for(), None);
+}
+
+#[test]
+fn soft_expiring_within_stale_if_error() {
+    // Stale since the epoch
+    let long_stale = SoftExpiring::parse(format!("{}1000#{{}}", FRESH_UNTIL_PREFIX));
+    let just_stale = SoftExpiring::parse(SoftExpiring::wrap("{}", 0));
+    let fresh = SoftExpiring::parse(SoftExpiring::wrap("{}", 60 * 1000));
+
+    assert!(!long_stale.within_stale_if_error(60 * 1000));
+    assert!(just_stale.within_stale_if_error(60 * 1000));
+    assert!(!just_stale.within_stale_if_error(0));
+    assert!(!fresh.within_stale_if_error(60 * 1000));
+}
@@ -0,0 +1,93 @@
+use crate::cache::cache_operations::{InvalidationPattern, InvalidationScope};
+use crate::cache::tags::{identifier_tag, tags_for_key};
+use crate::config::{with_config, Config, ConfigSource};
+
+fn config() -> Config {
+    Config::from_source(&ConfigSource::from_pairs(&[
+        ("CONFIG_SERVICE_URI", "https://config-url-example.com"),
+        ("WEBHOOK_TOKEN", "webhook_token"),
+        ("REDIS_URI", "redis://localhost:6379"),
+        ("REDIS_URI_MAINNET", "redis://localhost:6379"),
+    ]))
+    .unwrap()
+}
+
+async fn tags_for(key: &str) -> Vec<String> {
+    with_config(config(), async { tags_for_key(key) }).await
+}
+
+#[rocket::async_test]
+async fn tags_for_response_key() {
+    let key = "c_resp_/v1/chains/4/safes/0x1230B3d59858296A31053C1b8562Ecf89A2f888b/balances/usd";
+
+    let expected = vec![
+        String::from("address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"),
+        String::from("entity:balances"),
+    ];
+
+    assert_eq!(expected, tags_for(key).await);
+}
+
+#[rocket::async_test]
+async fn tags_for_request_key() {
+    let key = "c_reqs_https://safe-transaction.rinkeby.gnosis.io/api/v1/multisig-transactions/0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621/";
+
+    let expected = vec![
+        String::from("entity:transactions"),
+        String::from("hash:0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621"),
+    ];
+
+    assert_eq!(expected, tags_for(key).await);
+}
+
+#[rocket::async_test]
+async fn tags_for_config_service_key() {
+    let key = "c_reqs_https://config-url-example.com/api/v1/chains/137/";
+
+    let expected = vec![String::from("entity:chains")];
+
+    assert_eq!(expected, tags_for(key).await);
+}
+
+#[test]
+fn identifier_tag_only_for_addresses_and_hashes() {
+    assert_eq!(
+        Some(String::from(
+            "address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"
+        )),
+        identifier_tag("0x1230B3d59858296A31053C1b8562Ecf89A2f888b")
+    );
+    assert_eq!(None, identifier_tag("0x1230"));
+    assert_eq!(None, identifier_tag("some_address"));
+    assert_eq!(
+        None,
+        identifier_tag("0x1230B3d59858296A31053C1b8562Ecf89A2f888z")
+    );
+}
+
+#[test]
+fn invalidation_pattern_to_tag() {
+    let address = String::from("0x1230B3d59858296A31053C1b8562Ecf89A2f888b");
+
+    assert_eq!(
+        Some(String::from(
+            "address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"
+        )),
+        InvalidationPattern::Balances(InvalidationScope::Both, address).to_tag()
+    );
+    assert_eq!(
+        None,
+        InvalidationPattern::Any(InvalidationScope::Both, String::from("some_value")).to_tag()
+    );
+    assert_eq!(
+        Some(String::from("entity:contracts")),
+        InvalidationPattern::Contracts.to_tag()
+    );
+    assert_eq!(
+        None,
+        InvalidationPattern::Tokens {
+            chain_id: String::from("4")
+        }
+        .to_tag()
+    );
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use mockall::predicate::eq;
+
+use crate::cache::memory::{glob_match, new_bounded_in_memory_cache, new_in_memory_cache};
+use crate::cache::two_tier::{
+    apply_invalidation_message, escape_glob, new_two_tier_cache, InvalidationMessage, INSTANCE_ID,
+};
+use crate::cache::{Cache, MockCache, CACHE_INVALIDATION_CHANNEL};
+
+#[rocket::async_test]
+async fn two_tier_fetch_populates_local_layer() {
+    let remote = Arc::new(new_in_memory_cache());
+    remote.create("key", "value", 60 * 1000).await;
+    let cache = new_two_tier_cache(remote.clone(), 10, 60 * 1000);
+
+    assert_eq!(cache.fetch("key").await, Some(String::from("value")));
+
+    // Removing the value from the remote layer only does not affect the local copy
+    remote.invalidate("key").await;
+    assert_eq!(cache.fetch("key").await, Some(String::from("value")));
+}
+
+#[rocket::async_test]
+async fn two_tier_fetch_caps_local_copy_at_remote_ttl() {
+    let remote = Arc::new(new_in_memory_cache());
+    remote.create("key", "value", 50).await;
+    let cache = new_two_tier_cache(remote.clone(), 10, 60 * 1000);
+
+    assert_eq!(cache.fetch("key").await, Some(String::from("value")));
+    let local_ttl = cache.fetch_with_ttl("key").await.and_then(|(_, ttl)| ttl);
+    assert!(local_ttl.unwrap() <= 50);
+}
+
+#[rocket::async_test]
+async fn two_tier_local_copies_report_the_remote_ttl() {
+    let remote = Arc::new(new_in_memory_cache());
+    remote.create("fetched", "value", 60 * 1000).await;
+    let cache = new_two_tier_cache(remote.clone(), 10, 1000);
+
+    cache.fetch("fetched").await;
+    cache.create("created", "value", 60 * 1000).await;
+    // Served by the local layer only
+    remote.invalidate("fetched").await;
+    remote.invalidate("created").await;
+
+    for key in &["fetched", "created"] {
+        let ttl = cache.fetch_with_ttl(key).await.and_then(|(_, ttl)| ttl);
+        assert!(ttl.unwrap() > 59 * 1000, "{}", key);
+    }
+}
+
+#[rocket::async_test]
+async fn two_tier_create_writes_both_layers() {
+    let remote = Arc::new(new_in_memory_cache());
+    let cache = new_two_tier_cache(remote.clone(), 10, 60 * 1000);
+
+    cache.create("key", "value", 60 * 1000).await;
+
+    assert_eq!(remote.fetch("key").await, Some(String::from("value")));
+    assert_eq!(cache.fetch("key").await, Some(String::from("value")));
+}
+
+#[rocket::async_test]
+async fn two_tier_invalidate_pattern_clears_both_layers() {
+    let remote = Arc::new(new_in_memory_cache());
+    let cache = new_two_tier_cache(remote.clone(), 10, 60 * 1000);
+    cache
+        .create("c_resp_/0x1230/balances", "1", 60 * 1000)
+        .await;
+    cache
+        .create("c_resp_/0x4560/balances", "2", 60 * 1000)
+        .await;
+
+    cache.invalidate_pattern("c_re*0x1230*").await;
+
+    assert_eq!(cache.fetch("c_resp_/0x1230/balances").await, None);
+    assert_eq!(remote.fetch("c_resp_/0x1230/balances").await, None);
+    assert_eq!(
+        cache.fetch("c_resp_/0x4560/balances").await,
+        Some(String::from("2"))
+    );
+}
+
+#[rocket::async_test]
+async fn two_tier_invalidations_are_broadcast() {
+    let mut remote = MockCache::new();
+    remote
+        .expect_invalidate_pattern()
+        .times(1)
+        .with(eq("c_re*0x1230*"))
+        .return_const(());
+    remote
+        .expect_publish()
+        .times(1)
+        .withf(|channel, message| {
+            let message: InvalidationMessage = serde_json::from_str(message).unwrap();
+            channel == CACHE_INVALIDATION_CHANNEL
+                && message.instance == *INSTANCE_ID
+                && message.pattern == "c_re*0x1230*"
+        })
+        .return_const(());
+    let cache = new_two_tier_cache(Arc::new(remote), 10, 60 * 1000);
+
+    cache.invalidate_pattern("c_re*0x1230*").await;
+}
+
+#[rocket::async_test]
+async fn two_tier_invalidations_of_locks_and_tags_are_not_broadcast() {
+    let mut remote = MockCache::new();
+    remote.expect_invalidate().times(2).return_const(());
+    remote.expect_publish().times(0);
+    let cache = new_two_tier_cache(Arc::new(remote), 10, 60 * 1000);
+
+    cache.invalidate("c_lock_c_reqs_https://tx.service").await;
+    cache.invalidate("c_tag_address:0x1230").await;
+}
+
+#[rocket::async_test]
+async fn invalidation_messages_of_other_instances_clear_local_layer() {
+    let local = new_bounded_in_memory_cache(10);
+    local
+        .create("c_resp_/0x1230/balances", "1", 60 * 1000)
+        .await;
+    local
+        .create("c_resp_/0x4560/balances", "2", 60 * 1000)
+        .await;
+    let message = |instance: &str| {
+        serde_json::to_string(&InvalidationMessage {
+            instance: instance.to_string(),
+            pattern: String::from("c_re*0x1230*"),
+        })
+        .unwrap()
+    };
+
+    // Already applied locally by the instance which published it
+    apply_invalidation_message(&local, &message(&INSTANCE_ID));
+    assert!(local.has_key("c_resp_/0x1230/balances").await);
+
+    apply_invalidation_message(&local, &message("other"));
+    assert!(!local.has_key("c_resp_/0x1230/balances").await);
+    assert!(local.has_key("c_resp_/0x4560/balances").await);
+}
+
+#[test]
+fn escape_glob_matches_key_only() {
+    assert_eq!(
+        escape_glob("c_resp_/v1/*?[a]\\"),
+        "c_resp_/v1/\\*\\?\\[a\\]\\\\"
+    );
+    assert_eq!(escape_glob("c_resp_/v1/chains"), "c_resp_/v1/chains");
+    assert!(glob_match(&escape_glob("c_resp_/v1/*"), "c_resp_/v1/*"));
+    assert!(!glob_match(
+        &escape_glob("c_resp_/v1/*"),
+        "c_resp_/v1/chains"
+    ));
+}
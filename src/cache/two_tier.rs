@@ -0,0 +1,207 @@
+use std::cmp::min;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::memory::{new_bounded_in_memory_cache, InMemoryCache};
+use crate::cache::redis::spawn_subscriber;
+use crate::cache::{Cache, CACHE_INVALIDATION_CHANNEL, CACHE_LOCK_PREFIX, CACHE_TAG_PREFIX};
+
+lazy_static! {
+    // Identifies the invalidations broadcast by this process, which are already applied locally
+    pub(super) static ref INSTANCE_ID: String = format!("{:016x}", rand::random::<u64>());
+}
+
+/// Invalidation broadcast to the L1 layers of the other gateway instances
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub(super) struct InvalidationMessage {
+    pub(super) instance: String,
+    pub(super) pattern: String,
+}
+
+/// [Cache] that keeps a bounded, short lived, process local copy (L1) of the values read from or
+/// written to another [Cache] (L2, usually Redis).
+///
+/// Plain values are served from L1 while they are present there; hashes always go to L2, as they
+/// are large and written in place (e.g. the token cache). Invalidations are applied to both layers,
+/// and published on [CACHE_INVALIDATION_CHANNEL] for the L1 layers of other gateway instances
+/// (see [TwoTierCache::listen_for_invalidations]).
+pub struct TwoTierCache {
+    local: Arc<InMemoryCache>,
+    remote: Arc<dyn Cache>,
+    local_duration: usize,
+}
+
+pub fn new_two_tier_cache(
+    remote: Arc<dyn Cache>,
+    local_max_entries: usize,
+    local_duration: usize,
+) -> TwoTierCache {
+    TwoTierCache {
+        local: Arc::new(new_bounded_in_memory_cache(local_max_entries)),
+        remote,
+        local_duration,
+    }
+}
+
+impl TwoTierCache {
+    /// Applies the invalidations published by other gateway instances on the Redis deployment at
+    /// `uri` to the local layer
+    pub fn listen_for_invalidations(&self, uri: &str) {
+        let local = self.local.clone();
+        spawn_subscriber(uri, CACHE_INVALIDATION_CHANNEL, move |payload| {
+            apply_invalidation_message(&local, &payload)
+        });
+    }
+
+    async fn broadcast_invalidation(&self, pattern: &str) {
+        if is_remote_only(pattern) {
+            return;
+        }
+        let message = InvalidationMessage {
+            instance: INSTANCE_ID.clone(),
+            pattern: pattern.to_string(),
+        };
+        let message = serde_json::to_string(&message).expect("Invalidation message serialization");
+        self.remote
+            .publish(CACHE_INVALIDATION_CHANNEL, &message)
+            .await
+    }
+}
+
+pub(super) fn apply_invalidation_message(local: &InMemoryCache, payload: &str) {
+    match serde_json::from_str::<InvalidationMessage>(payload) {
+        Ok(message) if message.instance == *INSTANCE_ID => {}
+        Ok(message) => local.remove_matching(&message.pattern),
+        Err(error) => log::warn!("Invalid invalidation message {}: {}", payload, error),
+    }
+}
+
+// Locks and tag sets only live in L2, so invalidating them concerns no other instance
+fn is_remote_only(key: &str) -> bool {
+    [CACHE_LOCK_PREFIX, CACHE_TAG_PREFIX]
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+/// Escapes the glob special characters of `key`, so that it only matches itself
+pub(super) fn escape_glob(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[rocket::async_trait]
+impl Cache for TwoTierCache {
+    async fn fetch(&self, id: &str) -> Option<String> {
+        String::from_utf8(self.fetch_bytes(id).await?).ok()
+    }
+
+    async fn fetch_bytes(&self, id: &str) -> Option<Vec<u8>> {
+        self.fetch_with_ttl(id).await.map(|(value, _)| value)
+    }
+
+    // Local copies never outlive the remote value, and report its TTL rather than their own
+    async fn fetch_with_ttl(&self, id: &str) -> Option<(Vec<u8>, Option<usize>)> {
+        if let Some(local) = self.local.fetch_with_ttl(id).await {
+            return Some(local);
+        }
+        let (value, ttl) = self.remote.fetch_with_ttl(id).await?;
+        let local_duration = ttl.map_or(self.local_duration, |ttl| min(ttl, self.local_duration));
+        if local_duration > 0 {
+            self.local.create_copy(id, &value, local_duration, ttl);
+        }
+        Some((value, ttl))
+    }
+
+    async fn create(&self, id: &str, dest: &str, timeout: usize) {
+        self.remote.create(id, dest, timeout).await;
+        self.local.create_copy(
+            id,
+            dest.as_bytes(),
+            min(timeout, self.local_duration),
+            Some(timeout),
+        );
+    }
+
+    async fn insert_in_hash(&self, hash: &str, id: &str, dest: &str) {
+        self.remote.insert_in_hash(hash, id, dest).await
+    }
+
+    async fn get_from_hash(&self, hash: &str, id: &str) -> Option<String> {
+        self.remote.get_from_hash(hash, id).await
+    }
+
+    async fn has_key(&self, id: &str) -> bool {
+        self.local.has_key(id).await || self.remote.has_key(id).await
+    }
+
+    async fn ttl(&self, id: &str) -> Option<usize> {
+        self.remote.ttl(id).await
+    }
+
+    async fn expire_entity(&self, id: &str, timeout: usize) {
+        self.local.invalidate(id).await;
+        self.remote.expire_entity(id, timeout).await;
+        self.broadcast_invalidation(&escape_glob(id)).await
+    }
+
+    async fn try_lock(&self, id: &str, token: &str, timeout: usize) -> bool {
+        self.remote.try_lock(id, token, timeout).await
+    }
+
+    // Locks only live in L2, so there is nothing to broadcast
+    async fn unlock(&self, id: &str, token: &str) {
+        self.remote.unlock(id, token).await
+    }
+
+    async fn create_tagged(&self, id: &str, dest: &[u8], tags: &[String], timeout: usize) {
+        self.remote.create_tagged(id, dest, tags, timeout).await;
+        self.local
+            .create_copy(id, dest, min(timeout, self.local_duration), Some(timeout));
+    }
+
+    async fn invalidate_tagged(&self, tag: &str, pattern: &str) {
+        self.local.invalidate_pattern(pattern).await;
+        self.remote.invalidate_tagged(tag, pattern).await;
+        self.broadcast_invalidation(pattern).await
+    }
+
+    async fn tagged_keys(&self, tag: &str, pattern: &str, limit: usize) -> Vec<String> {
+        self.remote.tagged_keys(tag, pattern, limit).await
+    }
+
+    async fn keys(&self, pattern: &str, limit: usize) -> Vec<String> {
+        self.remote.keys(pattern, limit).await
+    }
+
+    async fn invalidate_pattern(&self, pattern: &str) {
+        self.local.invalidate_pattern(pattern).await;
+        self.remote.invalidate_pattern(pattern).await;
+        self.broadcast_invalidation(pattern).await
+    }
+
+    async fn invalidate(&self, id: &str) {
+        self.local.invalidate(id).await;
+        self.remote.invalidate(id).await;
+        self.broadcast_invalidation(&escape_glob(id)).await
+    }
+
+    async fn publish(&self, channel: &str, message: &str) {
+        self.remote.publish(channel, message).await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.remote.is_available().await
+    }
+
+    async fn info(&self) -> Option<String> {
+        self.remote.info().await
+    }
+}
@@ -1,192 +1,450 @@
-use std::env;
-use std::str::FromStr;
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
 
+use lazy_static::lazy_static;
+use rocket::tokio::task::JoinHandle;
+
+mod reload;
+mod settings;
 #[cfg(test)]
 mod tests;
 
-pub fn redis_uri() -> String {
-    env::var("REDIS_URI").expect("REDIS_URI missing in env")
+pub use reload::{reload, ReloadOnHangup};
+pub use settings::{load_dotenv, Config, ConfigError, ConfigSource, CHAIN_SETTINGS};
+
+lazy_static! {
+    static ref INSTALLED: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+    // Read from the environment while none is installed, along with the environment it was read
+    // from so that it is only read again when the environment changes
+    static ref FROM_ENV: Mutex<Option<(ConfigSource, Arc<Config>)>> = Mutex::new(None);
+}
+
+rocket::tokio::task_local! {
+    static SCOPED: Arc<Config>;
+}
+
+/// Makes `config` the configuration returned by [current], and so by every getter of this module
+pub fn install(config: Arc<Config>) {
+    *INSTALLED.write().expect("Config lock poisoned") = Some(config);
+}
+
+/// Configuration of the current task (see [with_config]), or else the installed one. Until one is
+/// installed, e.g. in tests, it is read from the environment whenever that changes, with missing
+/// required values read as empty.
+///
+/// Panics if a value in the environment is invalid.
+pub fn current() -> Arc<Config> {
+    if let Ok(config) = SCOPED.try_with(Arc::clone) {
+        return config;
+    }
+    if let Some(config) = INSTALLED.read().expect("Config lock poisoned").as_ref() {
+        return config.clone();
+    }
+    from_env()
+}
+
+fn from_env() -> Arc<Config> {
+    let source = ConfigSource::from_env();
+    let mut from_env = FROM_ENV.lock().expect("Config lock poisoned");
+    match from_env.as_ref() {
+        Some((read, config)) if *read == source => config.clone(),
+        _ => {
+            let config =
+                Config::from_source_incomplete(&source).unwrap_or_else(|error| panic!("{}", error));
+            let config = Arc::new(config);
+            *from_env = Some((source, config.clone()));
+            config
+        }
+    }
 }
 
-pub fn redis_uri_mainnet() -> String {
-    env::var("REDIS_URI_MAINNET").expect("REDIS_URI_MAINNET missing in env")
+/// Configuration of `chain_id`: the current one with the overrides of the chain applied
+pub fn for_chain(chain_id: &str) -> Arc<Config> {
+    chain_config(&current(), chain_id)
+}
+
+/// `config` with the overrides of `chain_id` applied
+pub fn chain_config(config: &Arc<Config>, chain_id: &str) -> Arc<Config> {
+    config.for_chain(chain_id).unwrap_or(config).clone()
+}
+
+/// Runs `future` with `config` as its configuration, without affecting the rest of the process.
+/// Tasks it spawns only keep it if spawned with [spawn].
+pub async fn with_config<F: Future>(config: Config, future: F) -> F::Output {
+    SCOPED.scope(Arc::new(config), future).await
+}
+
+/// Spawns `future` as a task running with the configuration of the caller, see [with_config]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match SCOPED.try_with(Arc::clone) {
+        Ok(config) => rocket::tokio::spawn(SCOPED.scope(config, future)),
+        Err(_) => rocket::tokio::spawn(future),
+    }
+}
+
+/// URI of the named cache backend `name`, e.g. `REDIS_URI_MAINNET` for `mainnet`
+pub fn redis_uri_for(name: &str) -> Option<String> {
+    current()
+        .named_redis_uris
+        .get(&name.to_lowercase())
+        .cloned()
+}
+
+pub fn redis_pool_size() -> u32 {
+    current().redis_pool_size
+}
+
+pub fn redis_pool_size_for(name: &str) -> u32 {
+    let config = current();
+    config
+        .named_redis_pool_sizes
+        .get(&name.to_lowercase())
+        .copied()
+        .unwrap_or(config.redis_pool_size)
+}
+
+/// Chain ids served by named cache backends, as `name=chain_id,chain_id;name=chain_id`. Chains
+/// not listed use the default backend.
+pub fn cache_routes() -> String {
+    current().cache_routes.clone()
 }
 
 pub fn base_config_service_uri() -> String {
-    format!(
-        "{}{}",
-        env::var("CONFIG_SERVICE_URI").expect("CONFIG_SERVICE_URI missing in env"),
-        "/api"
-    )
+    current().base_config_service_uri()
 }
 
-pub fn base_exchange_api_uri() -> String {
-    format!(
-        "{}?access_key={}",
-        env::var("EXCHANGE_API_BASE_URI").unwrap(),
-        env::var("EXCHANGE_API_KEY").unwrap()
-    )
+pub fn base_exchange_api_uri() -> Option<String> {
+    current().base_exchange_api_uri()
 }
 
 pub fn webhook_token() -> String {
-    env::var("WEBHOOK_TOKEN").expect("WEBHOOK_TOKEN missing in env")
+    current().webhook_token.clone()
 }
 
 pub fn scheme() -> String {
-    env_with_default("SCHEME", "https".into())
+    current().scheme.clone()
 }
 
 // TIME DURATION VALUES
-fn indefinite_timeout() -> usize {
-    env_with_default("INDEFINITE_TIMEOUT", 60 * 60 * 1000)
-}
-
 pub fn short_error_duration() -> usize {
-    env_with_default("SHORT_ERROR_DURATION", 60 * 1000)
+    current().short_error_duration
 }
 
 pub fn long_error_duration() -> usize {
-    env_with_default("LONG_ERROR_DURATION", 60 * 15 * 1000)
+    current().long_error_duration
+}
+
+pub fn unknown_contract_cache_duration() -> usize {
+    current().unknown_contract_cache_duration
 }
 
 // FUNCTIONAL TIMEOUTS
 pub fn safe_info_cache_duration() -> usize {
-    env_with_default("SAFE_INFO_CACHE_DURATION", indefinite_timeout())
+    current().safe_info_cache_duration
 }
 
 pub fn address_info_cache_duration() -> usize {
-    env_with_default("ADDRESS_INFO_CACHE_DURATION", indefinite_timeout())
+    current().address_info_cache_duration
 }
 
 pub fn token_info_cache_duration() -> usize {
-    env_with_default("TOKEN_INFO_CACHE_DURATION", 60 * 60 * 24 * 1000)
+    current().token_info_cache_duration
 }
 
 pub fn chain_info_cache_duration() -> usize {
-    env_with_default("CHAIN_INFO_CACHE_DURATION", indefinite_timeout())
+    current().chain_info_cache_duration
 }
 
 pub fn chain_info_response_cache_duration() -> usize {
-    env_with_default("CHAIN_INFO_RESPONSE_CACHE_DURATION", 1) // set to negligible value
+    current().chain_info_response_cache_duration
 }
 
 pub fn exchange_api_cache_duration() -> usize {
-    env_with_default("EXCHANGE_API_CACHE_DURATION", 60 * 60 * 12 * 1000)
+    current().exchange_api_cache_duration
 }
 
 pub fn request_cache_duration() -> usize {
-    env_with_default("REQUEST_CACHE_DURATION", indefinite_timeout())
+    current().request_cache_duration
 }
 
 pub fn about_cache_duration() -> usize {
-    env_with_default("ABOUT_CACHE_DURATION", 60 * 15 * 1000)
+    current().about_cache_duration
 }
 
 pub fn balances_cache_duration() -> usize {
-    env_with_default("BALANCES_REQUEST_CACHE_DURATION", 60 * 1000)
+    current().balances_cache_duration
 }
 
 pub fn balances_core_request_cache_duration() -> usize {
-    env_with_default("BALANCES_CORE_REQUEST_CACHE_DURATION", indefinite_timeout())
+    current().balances_core_request_cache_duration
 }
 
 pub fn owners_for_safes_cache_duration() -> usize {
-    env_with_default("OWNERS_FOR_SAFES_CACHE_DURATION", 60 * 1000)
+    current().owners_for_safes_cache_duration
 }
 
 pub fn safe_apps_cache_duration() -> usize {
-    env_with_default("SAFE_APPS_CACHE_DURATION", indefinite_timeout())
+    current().safe_apps_cache_duration
 }
 
 pub fn token_price_cache_duration() -> usize {
-    env_with_default("TOKEN_PRICE_CACHE_DURATION", 10 * 1000)
+    current().token_price_cache_duration
 }
 
 pub fn tx_queued_cache_duration() -> usize {
-    env_with_default("TX_QUEUED_CACHE_DURATION", request_cache_duration())
+    current().tx_queued_cache_duration
+}
+
+pub fn stale_while_revalidate_duration() -> usize {
+    current().stale_while_revalidate_duration
+}
+
+pub fn stale_if_error_duration() -> usize {
+    current().stale_if_error_duration
+}
+
+/// Reads within [refresh_ahead_window] after which a cached request is refreshed in the background
+/// before it expires, 0 to disable refresh-ahead
+pub fn refresh_ahead_hits() -> usize {
+    current().refresh_ahead_hits
+}
+
+pub fn refresh_ahead_window() -> u64 {
+    current().refresh_ahead_window
+}
+
+/// Share of the cache duration, in percent, left before expiry when hot entries are refreshed
+pub fn refresh_ahead_percentage() -> usize {
+    current().refresh_ahead_percentage
+}
+
+pub fn refresh_ahead_tracked_keys() -> usize {
+    current().refresh_ahead_tracked_keys
 }
 
 // REQUEST TIMEOUTS
 pub fn internal_client_connect_timeout() -> u64 {
-    env_with_default("INTERNAL_CLIENT_CONNECT_TIMEOUT", 1000)
+    current().internal_client_connect_timeout
 }
 
 pub fn safe_app_info_request_timeout() -> u64 {
-    env_with_default("SAFE_APP_INFO_REQUEST_TIMEOUT", 3000)
+    current().safe_app_info_request_timeout
 }
 
 pub fn transaction_request_timeout() -> u64 {
-    env_with_default("TRANSACTION_REQUEST_TIMEOUT", 30000)
+    current().transaction_request_timeout
 }
 
 pub fn safe_info_request_timeout() -> u64 {
-    env_with_default("SAFE_INFO_REQUEST_TIMEOUT", 10000)
+    current().safe_info_request_timeout
 }
 
 pub fn token_info_request_timeout() -> u64 {
-    env_with_default("TOKEN_INFO_REQUEST_TIMEOUT", 15000)
+    current().token_info_request_timeout
 }
 
 pub fn chain_info_request_timeout() -> u64 {
-    env_with_default("CHAIN_INFO_REQUEST_TIMEOUT", 15000)
+    current().chain_info_request_timeout
 }
 
 pub fn contract_info_request_timeout() -> u64 {
-    env_with_default("CONTRACT_INFO_REQUEST_TIMEOUT", 3000)
+    current().contract_info_request_timeout
 }
 
 pub fn balances_request_timeout() -> u64 {
-    env_with_default("BALANCES_REQUEST_TIMEOUT", 20000)
+    current().balances_request_timeout
 }
 
 pub fn collectibles_request_timeout() -> u64 {
-    env_with_default("COLLECTIBLES_REQUEST_TIMEOUT", 20000)
+    current().collectibles_request_timeout
 }
 
 pub fn default_request_timeout() -> u64 {
-    env_with_default("DEFAULT_REQUEST_TIMEOUT", 10000)
+    current().default_request_timeout
+}
+
+// RETRIES
+/// Retries of idempotent upstream requests failing transiently, within their timeout
+pub fn http_retry_max_retries() -> u32 {
+    current().http_retry_max_retries
+}
+
+/// Delay before the first retry, doubling on every further one
+pub fn http_retry_base_delay() -> u64 {
+    current().http_retry_base_delay
+}
+
+pub fn http_retry_max_delay() -> u64 {
+    current().http_retry_max_delay
+}
+
+// CIRCUIT BREAKER
+pub fn circuit_breaker_enabled() -> bool {
+    current().circuit_breaker_enabled
+}
+
+/// Share of failed requests to an upstream host, within a window, opening its circuit
+pub fn circuit_breaker_failure_rate() -> f64 {
+    current().circuit_breaker_failure_rate
+}
+
+/// Requests to an upstream host needed within a window before its circuit can open
+pub fn circuit_breaker_min_requests() -> u32 {
+    current().circuit_breaker_min_requests
+}
+
+pub fn circuit_breaker_window() -> u64 {
+    current().circuit_breaker_window
+}
+
+/// How long requests fail fast before a probe request is let through
+pub fn circuit_breaker_open_duration() -> u64 {
+    current().circuit_breaker_open_duration
+}
+
+// CONCURRENCY
+/// Requests in flight to the upstream `host`, 0 for no limit
+pub fn upstream_max_concurrent_requests(host: &str) -> usize {
+    let config = current();
+    config
+        .upstream_host_max_concurrent_requests
+        .limit_for(host)
+        .unwrap_or(config.upstream_max_concurrent_requests)
+}
+
+/// How long a request over the limit of its host waits before failing
+pub fn upstream_queue_timeout() -> u64 {
+    current().upstream_queue_timeout
 }
 
 // ERRORS
 pub fn request_error_cache_duration() -> usize {
-    env_with_default("REQS_ERROR_CACHE_DURATION", short_error_duration())
+    current().request_error_cache_duration
 }
 
 pub fn log_all_error_responses() -> bool {
-    env_with_default("LOG_ALL_ERROR_RESPONSES", false)
+    current().log_all_error_responses
 }
 
 // OTHERS
+pub fn cache_backend() -> String {
+    current().cache_backend.clone()
+}
+
+pub fn l1_cache_enabled() -> bool {
+    current().l1_cache_enabled
+}
+
+pub fn l1_cache_max_entries() -> usize {
+    current().l1_cache_max_entries
+}
+
+pub fn l1_cache_duration() -> usize {
+    current().l1_cache_duration
+}
+
+pub fn request_coalescing_enabled() -> bool {
+    current().request_coalescing_enabled
+}
+
+pub fn request_coalescing_distributed() -> bool {
+    current().request_coalescing_distributed
+}
+
+pub fn request_coalescing_poll_interval() -> u64 {
+    current().request_coalescing_poll_interval
+}
+
+pub fn cache_warm_up_enabled() -> bool {
+    current().cache_warm_up_enabled
+}
+
+/// Chains whose caches are warmed up, as `chain_id,chain_id`
+pub fn cache_warm_up_chains() -> String {
+    current().cache_warm_up_chains.clone()
+}
+
+/// Client URLs whose safe apps are warmed up, as `url,url`, matching the `client_url` sent by
+/// the clients
+pub fn cache_warm_up_client_urls() -> String {
+    current().cache_warm_up_client_urls.clone()
+}
+
+/// Interval between scheduled warm-ups, 0 to only warm up at startup and after flushes
+pub fn cache_warm_up_interval() -> u64 {
+    current().cache_warm_up_interval
+}
+
 pub fn redis_scan_count() -> usize {
-    env_with_default("REDIS_SCAN_COUNT", 300)
+    current().redis_scan_count
+}
+
+/// Whether tagged invalidations also scan the keyspace, for the entries cached before keys were
+/// tagged. Enabled by default until those are gone, see `.env.sample`.
+pub fn cache_invalidation_scan_fallback() -> bool {
+    current().cache_invalidation_scan_fallback
+}
+
+/// Cache values of at least this many bytes are stored compressed, 0 to disable compression.
+/// Releases before compression cannot read compressed values, so this must stay 0 until every
+/// instance sharing the cache runs a release supporting it.
+pub fn cache_compression_threshold() -> usize {
+    current().cache_compression_threshold
+}
+
+pub fn cache_compression_level() -> i32 {
+    current().cache_compression_level
+}
+
+/// Version of the gateway models stored in the cache, part of the keys of cached responses so that
+/// a release never serves responses serialized by another one. Defaults to the release version.
+pub fn cache_schema_version() -> String {
+    current().cache_schema_version.clone()
+}
+
+/// Longer cache keys are truncated and suffixed with a hash of the full key
+pub fn cache_key_max_length() -> usize {
+    current().cache_key_max_length
+}
+
+pub fn redis_connection_timeout() -> u64 {
+    current().redis_connection_timeout
+}
+
+pub fn redis_reconnect_interval() -> u64 {
+    current().redis_reconnect_interval
 }
 
 pub fn token_cache_size_count() -> usize {
-    env_with_default("TOKEN_CACHE_SIZE_COUNT", 20000)
+    current().token_cache_size_count
 }
 
 pub fn feature_flag_nested_decoding() -> bool {
-    env_with_default("FEATURE_FLAG_NESTED_DECODING", true)
+    current().feature_flag_nested_decoding
 }
 
 pub fn feature_flag_balances_rate_implementation() -> bool {
-    env_with_default("FEATURE_FLAG_BALANCES_RATE_IMPLEMENTATION", false)
+    current().feature_flag_balances_rate_implementation
 }
 
 pub fn is_messages_feature_enabled() -> bool {
-    env_with_default("FEATURE_MESSAGES", false)
+    current().is_messages_feature_enabled
 }
 
 pub fn vpc_transaction_service_uri() -> bool {
-    env_with_default("VPC_TRANSACTION_SERVICE_URI", true)
+    current().vpc_transaction_service_uri
 }
 
 pub fn concurrent_balance_token_requests() -> usize {
-    env_with_default("CONCURRENT_BALANCE_TOKEN_REQUESTS", 5)
+    current().concurrent_balance_token_requests
 }
 
 pub fn log_threshold() -> f32 {
-    env_with_default("LOG_THRESHOLD", 1.0)
+    current().log_threshold
 }
 
 pub fn build_number() -> Option<String> {
@@ -198,15 +456,3 @@ pub fn version() -> String {
         .unwrap_or(env!("CARGO_PKG_VERSION"))
         .to_string()
 }
-
-fn env_with_default<T: FromStr>(key: &str, default: T) -> T
-where
-    <T as FromStr>::Err: std::fmt::Debug,
-{
-    match env::var(key) {
-        Ok(value) => value
-            .parse()
-            .expect(&format!("Parsing of {} env var key failed", &key)),
-        Err(_) => default,
-    }
-}
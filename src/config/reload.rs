@@ -0,0 +1,68 @@
+use std::sync::{Arc, Mutex};
+
+use lazy_static::lazy_static;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Orbit, Rocket};
+
+use super::{current, install, Config, ConfigError};
+
+lazy_static! {
+    static ref RELOAD: Mutex<()> = Mutex::new(());
+}
+
+/// Reads the configuration again and, once validated, swaps it for the current one. Settings only
+/// read at startup keep their running value (see [Config::retain_startup_settings]), the keys of
+/// those that changed are returned.
+///
+/// The `CONFIG_FILE` and `.env` files are read again, but variables set in the environment of the
+/// process keep the value it was started with. Concurrent reloads run one at a time, so that the
+/// last one read is the one installed.
+pub fn reload() -> Result<Vec<&'static str>, ConfigError> {
+    let _reloading = RELOAD.lock().expect("Reload lock poisoned");
+    let mut config = Config::load()?;
+    let restart_required = config.retain_startup_settings(&current());
+    install(Arc::new(config));
+    Ok(restart_required)
+}
+
+/// Reloads the configuration whenever the process receives a `SIGHUP`
+pub struct ReloadOnHangup();
+
+#[rocket::async_trait]
+impl Fairing for ReloadOnHangup {
+    fn info(&self) -> Info {
+        Info {
+            name: "Reload configuration on SIGHUP",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, _rocket: &Rocket<Orbit>) {
+        #[cfg(unix)]
+        {
+            use rocket::tokio::signal::unix::{signal, SignalKind};
+
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(error) => {
+                    log::warn!("Listening for SIGHUP failed: {}", error);
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    match reload() {
+                        Ok(restart_required) if restart_required.is_empty() => {
+                            log::info!("Configuration reloaded")
+                        }
+                        Ok(restart_required) => log::warn!(
+                            "Configuration reloaded, changes to {} require a restart",
+                            restart_required.join(", ")
+                        ),
+                        Err(error) => log::error!("Configuration reload failed: {}", error),
+                    }
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,738 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::{env, fs};
+
+use lazy_static::lazy_static;
+use serde::{Serialize, Serializer};
+
+use crate::cache::error_policy::{ErrorCachePolicy, ErrorStatus};
+use crate::cache::manager::parse_cache_routes;
+use crate::cache::redis::RedisTopology;
+use crate::providers::warm_up::parse_list;
+use crate::utils::concurrency_limit::HostLimits;
+
+const REDACTED: &str = "<redacted>";
+
+lazy_static! {
+    // The `.env` file loaded at startup and the keys it set in the environment, see [load_dotenv]
+    static ref DOTENV: Mutex<Option<(PathBuf, HashSet<String>)>> = Mutex::new(None);
+}
+
+/// Loads the `.env` file, if any, into the environment without overriding variables already set.
+/// [Config::load] reads that file again instead of the values it set, so that changes to it are
+/// picked up on reload.
+pub fn load_dotenv() {
+    let startup_keys: HashSet<String> = env::vars().map(|(key, _)| key).collect();
+    if let Ok(path) = dotenv::dotenv() {
+        let dotenv_keys = env::vars()
+            .map(|(key, _)| key)
+            .filter(|key| !startup_keys.contains(key))
+            .collect();
+        *DOTENV.lock().expect("Dotenv lock poisoned") = Some((path, dotenv_keys));
+    }
+}
+
+/// Typed configuration of the gateway, read once (see [Config::load]) instead of on every access.
+///
+/// Fields are named after their keys: `redis_pool_size` is read from `REDIS_POOL_SIZE`, except
+/// where noted.
+///
+/// Secrets, including the Redis URIs which may hold credentials, are redacted when serialized.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Config {
+    // REDIS
+    #[serde(serialize_with = "redact_option")]
+    pub redis_uri: Option<String>,
+    pub redis_pool_size: u32,
+    /// Named cache backends by lowercase name, from `REDIS_URI_<NAME>`
+    #[serde(serialize_with = "redact_values")]
+    pub named_redis_uris: HashMap<String, String>,
+    /// From `REDIS_POOL_SIZE_<NAME>`, defaults to [Config::redis_pool_size]
+    pub named_redis_pool_sizes: HashMap<String, u32>,
+    pub cache_routes: String,
+
+    // SERVICES
+    pub config_service_uri: String,
+    pub exchange_api_base_uri: Option<String>,
+    #[serde(serialize_with = "redact_option")]
+    pub exchange_api_key: Option<String>,
+    #[serde(serialize_with = "redact")]
+    pub webhook_token: String,
+    pub scheme: String,
+
+    // CACHE DURATIONS
+    pub indefinite_timeout: usize,
+    pub short_error_duration: usize,
+    pub long_error_duration: usize,
+    pub unknown_contract_cache_duration: usize,
+    pub safe_info_cache_duration: usize,
+    pub address_info_cache_duration: usize,
+    pub token_info_cache_duration: usize,
+    pub chain_info_cache_duration: usize,
+    pub chain_info_response_cache_duration: usize,
+    pub exchange_api_cache_duration: usize,
+    pub request_cache_duration: usize,
+    pub about_cache_duration: usize,
+    /// `BALANCES_REQUEST_CACHE_DURATION`
+    pub balances_cache_duration: usize,
+    pub balances_core_request_cache_duration: usize,
+    pub owners_for_safes_cache_duration: usize,
+    pub safe_apps_cache_duration: usize,
+    pub token_price_cache_duration: usize,
+    pub tx_queued_cache_duration: usize,
+    pub stale_while_revalidate_duration: usize,
+    pub stale_if_error_duration: usize,
+    pub refresh_ahead_hits: usize,
+    pub refresh_ahead_window: u64,
+    pub refresh_ahead_percentage: usize,
+    pub refresh_ahead_tracked_keys: usize,
+
+    // REQUEST TIMEOUTS
+    pub internal_client_connect_timeout: u64,
+    pub safe_app_info_request_timeout: u64,
+    pub transaction_request_timeout: u64,
+    pub safe_info_request_timeout: u64,
+    pub token_info_request_timeout: u64,
+    pub chain_info_request_timeout: u64,
+    pub contract_info_request_timeout: u64,
+    pub balances_request_timeout: u64,
+    pub collectibles_request_timeout: u64,
+    pub default_request_timeout: u64,
+
+    // RETRIES
+    pub http_retry_max_retries: u32,
+    pub http_retry_base_delay: u64,
+    pub http_retry_max_delay: u64,
+
+    // CIRCUIT BREAKER
+    pub circuit_breaker_enabled: bool,
+    pub circuit_breaker_failure_rate: f64,
+    pub circuit_breaker_min_requests: u32,
+    pub circuit_breaker_window: u64,
+    pub circuit_breaker_open_duration: u64,
+
+    // CONCURRENCY
+    pub upstream_max_concurrent_requests: usize,
+    /// Overrides of [Config::upstream_max_concurrent_requests] by host, as `host=limit;host=limit`
+    pub upstream_host_max_concurrent_requests: HostLimits,
+    pub upstream_queue_timeout: u64,
+
+    // ERRORS
+    /// `REQS_ERROR_CACHE_DURATION`
+    pub request_error_cache_duration: usize,
+    /// Cache durations of upstream errors by status, as `status=duration;status=duration`. A
+    /// status is a code (`404`), a class (`5xx`) or `timeout`. By default only client errors are
+    /// cached.
+    pub error_cache_policy: ErrorCachePolicy,
+    pub log_all_error_responses: bool,
+
+    // OTHERS
+    pub cache_backend: String,
+    pub l1_cache_enabled: bool,
+    pub l1_cache_max_entries: usize,
+    pub l1_cache_duration: usize,
+    pub request_coalescing_enabled: bool,
+    pub request_coalescing_distributed: bool,
+    pub request_coalescing_poll_interval: u64,
+    pub cache_warm_up_enabled: bool,
+    pub cache_warm_up_chains: String,
+    pub cache_warm_up_client_urls: String,
+    pub cache_warm_up_interval: u64,
+    pub redis_scan_count: usize,
+    pub cache_invalidation_scan_fallback: bool,
+    pub cache_compression_threshold: usize,
+    pub cache_compression_level: i32,
+    pub cache_schema_version: String,
+    pub cache_key_max_length: usize,
+    pub redis_connection_timeout: u64,
+    pub redis_reconnect_interval: u64,
+    pub token_cache_size_count: usize,
+    pub feature_flag_nested_decoding: bool,
+    pub feature_flag_balances_rate_implementation: bool,
+    /// `FEATURE_MESSAGES`
+    pub is_messages_feature_enabled: bool,
+    pub vpc_transaction_service_uri: bool,
+    pub concurrent_balance_token_requests: usize,
+    pub log_threshold: f32,
+
+    // METRICS
+    /// Chains routed in [Config::cache_routes] or warmed up in [Config::cache_warm_up_chains],
+    /// which get their own label in the cache metrics. Derived, not read from a key.
+    #[serde(skip)]
+    pub metrics_chain_ids: HashSet<String>,
+
+    // CHAINS
+    /// Configuration of the chains overriding any of [CHAIN_SETTINGS], by chain id, from
+    /// `CHAIN_<CHAIN_ID>_<KEY>`
+    #[serde(serialize_with = "serialize_chains")]
+    pub chains: HashMap<String, Arc<Config>>,
+}
+
+/// Keys that can be overridden per chain, e.g. with `CHAIN_137_TRANSACTION_REQUEST_TIMEOUT`. The
+/// defaults derived from them follow: `CHAIN_5_INDEFINITE_TIMEOUT` also applies to the cache
+/// durations of chain 5 that are not set explicitly.
+pub const CHAIN_SETTINGS: &[&str] = &[
+    "INDEFINITE_TIMEOUT",
+    "SHORT_ERROR_DURATION",
+    "LONG_ERROR_DURATION",
+    "UNKNOWN_CONTRACT_CACHE_DURATION",
+    "SAFE_INFO_CACHE_DURATION",
+    "ADDRESS_INFO_CACHE_DURATION",
+    "TOKEN_INFO_CACHE_DURATION",
+    "CHAIN_INFO_CACHE_DURATION",
+    "REQUEST_CACHE_DURATION",
+    "BALANCES_REQUEST_CACHE_DURATION",
+    "BALANCES_CORE_REQUEST_CACHE_DURATION",
+    "OWNERS_FOR_SAFES_CACHE_DURATION",
+    "SAFE_APPS_CACHE_DURATION",
+    "TOKEN_PRICE_CACHE_DURATION",
+    "TX_QUEUED_CACHE_DURATION",
+    "STALE_WHILE_REVALIDATE_DURATION",
+    "STALE_IF_ERROR_DURATION",
+    "SAFE_APP_INFO_REQUEST_TIMEOUT",
+    "TRANSACTION_REQUEST_TIMEOUT",
+    "SAFE_INFO_REQUEST_TIMEOUT",
+    "TOKEN_INFO_REQUEST_TIMEOUT",
+    "CHAIN_INFO_REQUEST_TIMEOUT",
+    "CONTRACT_INFO_REQUEST_TIMEOUT",
+    "BALANCES_REQUEST_TIMEOUT",
+    "COLLECTIBLES_REQUEST_TIMEOUT",
+    "DEFAULT_REQUEST_TIMEOUT",
+];
+
+impl Config {
+    /// Reads the TOML file at `CONFIG_FILE`, if set, overridden by the `.env` file loaded at
+    /// startup (see [load_dotenv]), itself overridden by the environment, and validates the result
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_source(&ConfigSource::load()?)
+    }
+
+    pub fn from_source(source: &ConfigSource) -> Result<Self, ConfigError> {
+        let (config, problems) = Self::read(source);
+        if problems.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(problems.errors))
+        }
+    }
+
+    /// Like [Config::from_source], but reading missing required values as empty. Only values
+    /// which are set must be valid.
+    pub fn from_source_incomplete(source: &ConfigSource) -> Result<Self, ConfigError> {
+        let (config, problems) = Self::read(source);
+        let invalid = problems.invalid();
+        if invalid.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(invalid))
+        }
+    }
+
+    /// Configuration of `chain_id`, if it overrides any of [CHAIN_SETTINGS]
+    pub fn for_chain(&self, chain_id: &str) -> Option<&Arc<Config>> {
+        self.chains.get(chain_id)
+    }
+
+    fn read(source: &ConfigSource) -> (Self, Problems) {
+        let (global, overrides) = source.split_chains();
+        let (mut config, mut problems) = Self::read_global(&global);
+        for (chain_id, settings) in overrides {
+            let mut chain_source = global.clone();
+            for (key, value) in settings {
+                if CHAIN_SETTINGS.contains(&key.as_str()) {
+                    chain_source.0.insert(key, value);
+                } else {
+                    problems.errors.push(format!(
+                        "CHAIN_{}_{}: not configurable per chain",
+                        chain_id, key
+                    ));
+                }
+            }
+            let (chain_config, chain_problems) = Self::read_global(&chain_source);
+            // Errors of the global settings were already reported
+            for error in chain_problems.errors {
+                if !problems.errors.contains(&error) {
+                    problems
+                        .errors
+                        .push(format!("CHAIN_{}_{}", chain_id, error));
+                }
+            }
+            config.chains.insert(chain_id, Arc::new(chain_config));
+        }
+        (config, problems)
+    }
+
+    fn read_global(source: &ConfigSource) -> (Self, Problems) {
+        let mut values = Values {
+            source,
+            problems: Problems::default(),
+        };
+        let indefinite_timeout = values.get("INDEFINITE_TIMEOUT", 60 * 60 * 1000);
+        let short_error_duration = values.get("SHORT_ERROR_DURATION", 60 * 1000);
+        let request_cache_duration = values.get("REQUEST_CACHE_DURATION", indefinite_timeout);
+        let request_error_cache_duration =
+            values.get("REQS_ERROR_CACHE_DURATION", short_error_duration);
+        let mut default_error_cache_policy = ErrorCachePolicy::default();
+        default_error_cache_policy.set(ErrorStatus::Class(4), request_error_cache_duration);
+        let redis_pool_size = values.get("REDIS_POOL_SIZE", 15);
+        let cache_routes = values.get("CACHE_ROUTES", "mainnet=1".into());
+        let cache_warm_up_chains = values.get("CACHE_WARM_UP_CHAINS", "1".into());
+        let metrics_chain_ids = metrics_chain_ids(&cache_routes, &cache_warm_up_chains);
+        let config = Config {
+            redis_uri: values.optional("REDIS_URI"),
+            redis_pool_size,
+            named_redis_uris: source.named("REDIS_URI_"),
+            named_redis_pool_sizes: source
+                .named("REDIS_POOL_SIZE_")
+                .into_keys()
+                .map(|name| {
+                    let key = format!("REDIS_POOL_SIZE_{}", name.to_uppercase());
+                    (name, values.get(&key, redis_pool_size))
+                })
+                .collect(),
+            cache_routes,
+            config_service_uri: values.required("CONFIG_SERVICE_URI"),
+            exchange_api_base_uri: values.optional("EXCHANGE_API_BASE_URI"),
+            exchange_api_key: values.optional("EXCHANGE_API_KEY"),
+            webhook_token: values.required("WEBHOOK_TOKEN"),
+            scheme: values.get("SCHEME", "https".into()),
+            indefinite_timeout,
+            short_error_duration,
+            long_error_duration: values.get("LONG_ERROR_DURATION", 60 * 15 * 1000),
+            unknown_contract_cache_duration: values
+                .get("UNKNOWN_CONTRACT_CACHE_DURATION", 60 * 60 * 6 * 1000),
+            safe_info_cache_duration: values.get("SAFE_INFO_CACHE_DURATION", indefinite_timeout),
+            address_info_cache_duration: values
+                .get("ADDRESS_INFO_CACHE_DURATION", indefinite_timeout),
+            token_info_cache_duration: values.get("TOKEN_INFO_CACHE_DURATION", 60 * 60 * 24 * 1000),
+            chain_info_cache_duration: values.get("CHAIN_INFO_CACHE_DURATION", indefinite_timeout),
+            chain_info_response_cache_duration: values.get("CHAIN_INFO_RESPONSE_CACHE_DURATION", 1),
+            exchange_api_cache_duration: values
+                .get("EXCHANGE_API_CACHE_DURATION", 60 * 60 * 12 * 1000),
+            request_cache_duration,
+            about_cache_duration: values.get("ABOUT_CACHE_DURATION", 60 * 15 * 1000),
+            balances_cache_duration: values.get("BALANCES_REQUEST_CACHE_DURATION", 60 * 1000),
+            balances_core_request_cache_duration: values
+                .get("BALANCES_CORE_REQUEST_CACHE_DURATION", indefinite_timeout),
+            owners_for_safes_cache_duration: values
+                .get("OWNERS_FOR_SAFES_CACHE_DURATION", 60 * 1000),
+            safe_apps_cache_duration: values.get("SAFE_APPS_CACHE_DURATION", indefinite_timeout),
+            token_price_cache_duration: values.get("TOKEN_PRICE_CACHE_DURATION", 10 * 1000),
+            tx_queued_cache_duration: values
+                .get("TX_QUEUED_CACHE_DURATION", request_cache_duration),
+            stale_while_revalidate_duration: values.get("STALE_WHILE_REVALIDATE_DURATION", 0),
+            stale_if_error_duration: values.get("STALE_IF_ERROR_DURATION", 0),
+            refresh_ahead_hits: values.get("REFRESH_AHEAD_HITS", 100),
+            refresh_ahead_window: values.get("REFRESH_AHEAD_WINDOW", 60 * 1000),
+            refresh_ahead_percentage: values.get("REFRESH_AHEAD_PERCENTAGE", 10),
+            refresh_ahead_tracked_keys: values.get("REFRESH_AHEAD_TRACKED_KEYS", 10000),
+            internal_client_connect_timeout: values.get("INTERNAL_CLIENT_CONNECT_TIMEOUT", 1000),
+            safe_app_info_request_timeout: values.get("SAFE_APP_INFO_REQUEST_TIMEOUT", 3000),
+            transaction_request_timeout: values.get("TRANSACTION_REQUEST_TIMEOUT", 30000),
+            safe_info_request_timeout: values.get("SAFE_INFO_REQUEST_TIMEOUT", 10000),
+            token_info_request_timeout: values.get("TOKEN_INFO_REQUEST_TIMEOUT", 15000),
+            chain_info_request_timeout: values.get("CHAIN_INFO_REQUEST_TIMEOUT", 15000),
+            contract_info_request_timeout: values.get("CONTRACT_INFO_REQUEST_TIMEOUT", 3000),
+            balances_request_timeout: values.get("BALANCES_REQUEST_TIMEOUT", 20000),
+            collectibles_request_timeout: values.get("COLLECTIBLES_REQUEST_TIMEOUT", 20000),
+            default_request_timeout: values.get("DEFAULT_REQUEST_TIMEOUT", 10000),
+            http_retry_max_retries: values.get("HTTP_RETRY_MAX_RETRIES", 2),
+            http_retry_base_delay: values.get("HTTP_RETRY_BASE_DELAY", 100),
+            http_retry_max_delay: values.get("HTTP_RETRY_MAX_DELAY", 2000),
+            circuit_breaker_enabled: values.get("CIRCUIT_BREAKER_ENABLED", true),
+            circuit_breaker_failure_rate: values.get("CIRCUIT_BREAKER_FAILURE_RATE", 0.5),
+            circuit_breaker_min_requests: values.get("CIRCUIT_BREAKER_MIN_REQUESTS", 20),
+            circuit_breaker_window: values.get("CIRCUIT_BREAKER_WINDOW", 30000),
+            circuit_breaker_open_duration: values.get("CIRCUIT_BREAKER_OPEN_DURATION", 30000),
+            upstream_max_concurrent_requests: values.get("UPSTREAM_MAX_CONCURRENT_REQUESTS", 100),
+            upstream_host_max_concurrent_requests: values.get(
+                "UPSTREAM_HOST_MAX_CONCURRENT_REQUESTS",
+                HostLimits::default(),
+            ),
+            upstream_queue_timeout: values.get("UPSTREAM_QUEUE_TIMEOUT", 1000),
+            request_error_cache_duration,
+            error_cache_policy: values.get("ERROR_CACHE_POLICY", default_error_cache_policy),
+            log_all_error_responses: values.get("LOG_ALL_ERROR_RESPONSES", false),
+            cache_backend: values.get("CACHE_BACKEND", "redis".into()),
+            l1_cache_enabled: values.get("L1_CACHE_ENABLED", false),
+            l1_cache_max_entries: values.get("L1_CACHE_MAX_ENTRIES", 10000),
+            l1_cache_duration: values.get("L1_CACHE_DURATION", 5 * 1000),
+            request_coalescing_enabled: values.get("REQUEST_COALESCING_ENABLED", true),
+            request_coalescing_distributed: values.get("REQUEST_COALESCING_DISTRIBUTED", false),
+            request_coalescing_poll_interval: values.get("REQUEST_COALESCING_POLL_INTERVAL", 50),
+            cache_warm_up_enabled: values.get("CACHE_WARM_UP_ENABLED", false),
+            cache_warm_up_chains,
+            cache_warm_up_client_urls: values.get(
+                "CACHE_WARM_UP_CLIENT_URLS",
+                "https://app.safe.global".into(),
+            ),
+            cache_warm_up_interval: values.get("CACHE_WARM_UP_INTERVAL", 60 * 15 * 1000),
+            redis_scan_count: values.get("REDIS_SCAN_COUNT", 300),
+            cache_invalidation_scan_fallback: values.get("CACHE_INVALIDATION_SCAN_FALLBACK", true),
+            cache_compression_threshold: values.get("CACHE_COMPRESSION_THRESHOLD", 0),
+            cache_compression_level: values.get("CACHE_COMPRESSION_LEVEL", 3),
+            cache_schema_version: values.get("CACHE_SCHEMA_VERSION", default_schema_version()),
+            cache_key_max_length: values.get("CACHE_KEY_MAX_LENGTH", 256),
+            redis_connection_timeout: values.get("REDIS_CONNECTION_TIMEOUT", 1000),
+            redis_reconnect_interval: values.get("REDIS_RECONNECT_INTERVAL", 5000),
+            token_cache_size_count: values.get("TOKEN_CACHE_SIZE_COUNT", 20000),
+            feature_flag_nested_decoding: values.get("FEATURE_FLAG_NESTED_DECODING", true),
+            feature_flag_balances_rate_implementation: values
+                .get("FEATURE_FLAG_BALANCES_RATE_IMPLEMENTATION", false),
+            is_messages_feature_enabled: values.get("FEATURE_MESSAGES", false),
+            vpc_transaction_service_uri: values.get("VPC_TRANSACTION_SERVICE_URI", true),
+            concurrent_balance_token_requests: values.get("CONCURRENT_BALANCE_TOKEN_REQUESTS", 5),
+            log_threshold: values.get("LOG_THRESHOLD", 1.0),
+            metrics_chain_ids,
+            chains: HashMap::new(),
+        };
+        let mut problems = values.problems;
+        config.validate(&mut problems);
+        (config, problems)
+    }
+
+    /// Keeps the settings that are only read at startup, e.g. to connect to the cache backends,
+    /// at their value in `running`. Returns the keys whose new value requires a restart.
+    pub fn retain_startup_settings(&mut self, running: &Config) -> Vec<&'static str> {
+        let mut ignored = vec![];
+        macro_rules! retain {
+            ($($field:ident => $key:literal),* $(,)?) => {
+                $(
+                    if self.$field != running.$field {
+                        self.$field = Clone::clone(&running.$field);
+                        ignored.push($key);
+                    }
+                )*
+            };
+        }
+        retain!(
+            redis_uri => "REDIS_URI",
+            redis_pool_size => "REDIS_POOL_SIZE",
+            named_redis_uris => "REDIS_URI_<NAME>",
+            named_redis_pool_sizes => "REDIS_POOL_SIZE_<NAME>",
+            cache_routes => "CACHE_ROUTES",
+            cache_backend => "CACHE_BACKEND",
+            l1_cache_enabled => "L1_CACHE_ENABLED",
+            l1_cache_max_entries => "L1_CACHE_MAX_ENTRIES",
+            l1_cache_duration => "L1_CACHE_DURATION",
+            cache_warm_up_enabled => "CACHE_WARM_UP_ENABLED",
+            cache_schema_version => "CACHE_SCHEMA_VERSION",
+            refresh_ahead_tracked_keys => "REFRESH_AHEAD_TRACKED_KEYS",
+            internal_client_connect_timeout => "INTERNAL_CLIENT_CONNECT_TIMEOUT",
+        );
+        self.metrics_chain_ids = metrics_chain_ids(&self.cache_routes, &self.cache_warm_up_chains);
+        ignored
+    }
+
+    fn validate(&self, problems: &mut Problems) {
+        match self.cache_backend.as_str() {
+            "redis" if self.redis_uri.is_none() => problems.missing(String::from(
+                "REDIS_URI is required by the redis CACHE_BACKEND",
+            )),
+            "redis" | "memory" => {}
+            backend => problems
+                .errors
+                .push(format!("CACHE_BACKEND: unsupported backend {}", backend)),
+        }
+        let routes = parse_cache_routes(&self.cache_routes).unwrap_or_else(|error| {
+            problems.errors.push(format!("CACHE_ROUTES: {}", error));
+            vec![]
+        });
+        if self.cache_backend == "redis" {
+            for (name, _) in routes {
+                if !self.named_redis_uris.contains_key(&name.to_lowercase()) {
+                    problems.missing(format!(
+                        "REDIS_URI_{} is required by CACHE_ROUTES",
+                        name.to_uppercase()
+                    ));
+                }
+            }
+        }
+        let mut named_redis_uris: Vec<_> = self.named_redis_uris.iter().collect();
+        named_redis_uris.sort();
+        let redis_uris = self
+            .redis_uri
+            .iter()
+            .map(|uri| (String::from("REDIS_URI"), uri));
+        let named_redis_uris = named_redis_uris
+            .into_iter()
+            .map(|(name, uri)| (format!("REDIS_URI_{}", name.to_uppercase()), uri));
+        for (key, uri) in redis_uris.chain(named_redis_uris) {
+            if let Err(error) = RedisTopology::parse(uri) {
+                problems.errors.push(format!("{}: {}", key, error));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.log_threshold) {
+            problems
+                .errors
+                .push(String::from("LOG_THRESHOLD must be within [0.0, 1.0]"));
+        }
+        if self.circuit_breaker_failure_rate <= 0.0 || self.circuit_breaker_failure_rate > 1.0 {
+            problems.errors.push(String::from(
+                "CIRCUIT_BREAKER_FAILURE_RATE must be within ]0.0, 1.0]",
+            ));
+        }
+    }
+
+    pub fn base_config_service_uri(&self) -> String {
+        format!("{}{}", self.config_service_uri, "/api")
+    }
+
+    /// [None] unless both `EXCHANGE_API_BASE_URI` and `EXCHANGE_API_KEY` are set
+    pub fn base_exchange_api_uri(&self) -> Option<String> {
+        Some(format!(
+            "{}?access_key={}",
+            self.exchange_api_base_uri.as_ref()?,
+            self.exchange_api_key.as_ref()?
+        ))
+    }
+}
+
+// Malformed CACHE_ROUTES are reported by [Config::validate]
+fn metrics_chain_ids(cache_routes: &str, cache_warm_up_chains: &str) -> HashSet<String> {
+    parse_cache_routes(cache_routes)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|(_, chain_ids)| chain_ids)
+        .chain(parse_list(cache_warm_up_chains))
+        .collect()
+}
+
+fn default_schema_version() -> String {
+    match super::build_number() {
+        Some(build_number) => format!("{}-{}", super::version(), build_number),
+        None => super::version(),
+    }
+}
+
+fn redact<T, S: Serializer>(_: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+fn redact_option<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.as_ref().map(|_| REDACTED).serialize(serializer)
+}
+
+fn redact_values<S: Serializer>(
+    values: &HashMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(values.keys().map(|key| (key, REDACTED)))
+}
+
+fn serialize_chains<S: Serializer>(
+    chains: &HashMap<String, Arc<Config>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        chains
+            .iter()
+            .map(|(chain_id, config)| (chain_id, config.as_ref())),
+    )
+}
+
+/// Every problem found while reading a [Config], so they can all be fixed at once
+#[derive(Debug, PartialEq)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+// Problems found while reading a [Config], in the order they were found
+#[derive(Default)]
+struct Problems {
+    errors: Vec<String>,
+    // Those of `errors` caused by missing values
+    missing: Vec<String>,
+}
+
+impl Problems {
+    fn missing(&mut self, error: String) {
+        self.missing.push(error.clone());
+        self.errors.push(error);
+    }
+
+    // Errors caused by values which are set
+    fn invalid(self) -> Vec<String> {
+        let missing = self.missing;
+        self.errors
+            .into_iter()
+            .filter(|error| !missing.contains(error))
+            .collect()
+    }
+}
+
+/// `vars` with the values set from the `.env` file at `path` (`dotenv_keys`) read from it again,
+/// dropping those since removed from it
+pub(super) fn with_dotenv(
+    path: &Path,
+    dotenv_keys: &HashSet<String>,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<HashMap<String, String>, ConfigError> {
+    let read_failed = |error: dotenv::Error| {
+        ConfigError(vec![format!(
+            "Reading {} failed: {}",
+            path.display(),
+            error
+        )])
+    };
+    // Iterating keeps the file out of the environment, which the loading functions would not
+    // override with the values set at startup
+    #[allow(deprecated)]
+    let mut environment = dotenv::from_path_iter(path)
+        .map_err(read_failed)?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(read_failed)?;
+    environment.extend(vars.filter(|(key, _)| !dotenv_keys.contains(key)));
+    Ok(environment)
+}
+
+/// Raw configuration values by key, e.g. `REDIS_URI`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigSource(HashMap<String, String>);
+
+impl ConfigSource {
+    fn load() -> Result<Self, ConfigError> {
+        let environment = match DOTENV.lock().expect("Dotenv lock poisoned").as_ref() {
+            Some((path, dotenv_keys)) => with_dotenv(path, dotenv_keys, env::vars())?,
+            None => env::vars().collect(),
+        };
+        let mut source = match environment.get("CONFIG_FILE") {
+            Some(path) => Self::from_toml_file(path)?,
+            None => Self::default(),
+        };
+        source.0.extend(environment);
+        Ok(source)
+    }
+
+    pub fn from_env() -> Self {
+        ConfigSource(env::vars().collect())
+    }
+
+    pub fn from_pairs(pairs: &[(&str, &str)]) -> Self {
+        ConfigSource(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    fn from_toml_file(path: &str) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path)
+            .map_err(|error| ConfigError(vec![format!("Reading {} failed: {}", path, error)]))?;
+        Self::from_toml(&content)
+            .map_err(|error| ConfigError(vec![format!("Parsing {} failed: {}", path, error)]))
+    }
+
+    /// Keys are case insensitive, and those of nested tables are prefixed with the table name:
+    /// `[redis] pool_size = 15` is the same as `REDIS_POOL_SIZE = 15`
+    pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
+        let mut values = HashMap::new();
+        flatten_toml("", &toml::from_str(content)?, &mut values);
+        Ok(ConfigSource(values))
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .get(key)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    // Splits the `CHAIN_<CHAIN_ID>_<KEY>` entries from the rest, grouping them by chain id
+    fn split_chains(&self) -> (Self, HashMap<String, HashMap<String, String>>) {
+        let mut global = HashMap::new();
+        let mut chains: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (key, value) in &self.0 {
+            let chain_setting = key
+                .strip_prefix("CHAIN_")
+                .and_then(|rest| rest.split_once('_'))
+                // Keeps `CHAIN_INFO_CACHE_DURATION` and the like global
+                .filter(|(chain_id, _)| {
+                    !chain_id.is_empty() && chain_id.chars().all(|c| c.is_ascii_digit())
+                });
+            match chain_setting {
+                Some((chain_id, setting)) => {
+                    chains
+                        .entry(chain_id.to_string())
+                        .or_default()
+                        .insert(setting.to_string(), value.clone());
+                }
+                _ => {
+                    global.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        (ConfigSource(global), chains)
+    }
+
+    // Values of the keys starting with `prefix`, by the rest of their key in lowercase
+    fn named(&self, prefix: &str) -> HashMap<String, String> {
+        self.0
+            .iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix(prefix)?;
+                Some((name.to_lowercase(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+fn flatten_toml(prefix: &str, table: &toml::value::Table, values: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key.to_uppercase());
+        match value {
+            toml::Value::Table(table) => flatten_toml(&format!("{}_", key), table, values),
+            toml::Value::String(value) => {
+                values.insert(key, value.clone());
+            }
+            value => {
+                values.insert(key, value.to_string());
+            }
+        }
+    }
+}
+
+// Reads typed values, collecting the errors instead of failing on the first one
+struct Values<'s> {
+    source: &'s ConfigSource,
+    problems: Problems,
+}
+
+impl Values<'_> {
+    fn get<T: FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        <T as FromStr>::Err: fmt::Display,
+    {
+        match self.source.get(key).map(str::parse) {
+            Some(Ok(value)) => value,
+            Some(Err(error)) => {
+                self.problems.errors.push(format!("{}: {}", key, error));
+                default
+            }
+            None => default,
+        }
+    }
+
+    fn optional(&self, key: &str) -> Option<String> {
+        self.source.get(key).map(String::from)
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.optional(key).unwrap_or_else(|| {
+            self.problems.missing(format!("{} is required", key));
+            String::new()
+        })
+    }
+}
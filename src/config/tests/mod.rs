@@ -1,3 +1,5 @@
+mod settings;
+
 #[test]
 fn test_env() {
     for test_case in build_usize_test_cases() {
@@ -6,13 +6,15 @@ extern crate rocket;
 
 use std::sync::Arc;
 
-use dotenv::dotenv;
 use rocket::{Build, Rocket};
 
 use routes::active_routes;
+use utils::cache_headers::CacheHeaders;
 use utils::cors::CORS;
 
 use crate::cache::manager::{create_cache_manager, RedisCacheManager};
+use crate::config::{Config, ReloadOnHangup};
+use crate::providers::warm_up::CacheWarmUp;
 use crate::routes::error_catchers;
 use crate::utils::http_client::{setup_http_client, HttpClient};
 use rocket_okapi::swagger_ui::{make_swagger_ui, SwaggerUIConfig};
@@ -42,9 +44,12 @@ mod tests;
 #[doc(hidden)]
 #[launch]
 async fn rocket() -> Rocket<Build> {
-    dotenv().ok();
+    crate::config::load_dotenv();
     setup_logger();
 
+    let config = Config::load().unwrap_or_else(|error| panic!("{}", error));
+    crate::config::install(Arc::new(config));
+
     let client = setup_http_client();
     let cache_manager = create_cache_manager().await;
 
@@ -61,6 +66,9 @@ async fn rocket() -> Rocket<Build> {
         .manage(Arc::new(cache_manager) as Arc<dyn RedisCacheManager>)
         .manage(Arc::new(client) as Arc<dyn HttpClient>)
         .attach(monitoring::performance::PerformanceMonitor())
+        .attach(CacheHeaders())
+        .attach(CacheWarmUp())
+        .attach(ReloadOnHangup())
         .attach(CORS())
 }
 
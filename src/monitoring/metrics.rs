@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+// Upper bounds (in seconds) of the latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+type Labels = Vec<(&'static str, String)>;
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::default();
+}
+
+/// Process wide registry of counters, gauges and latency histograms, rendered in the Prometheus text
+/// exposition format.
+#[derive(Default)]
+struct Registry {
+    counters: Mutex<BTreeMap<&'static str, BTreeMap<Labels, u64>>>,
+    gauges: Mutex<BTreeMap<&'static str, BTreeMap<Labels, f64>>>,
+    histograms: Mutex<BTreeMap<&'static str, BTreeMap<Labels, Histogram>>>,
+}
+
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            buckets: [0; LATENCY_BUCKETS.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS.iter()) {
+            if value <= *upper_bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+fn to_labels(labels: &[(&'static str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(name, value)| (*name, value.to_string()))
+        .collect()
+}
+
+pub fn increment_counter(name: &'static str, labels: &[(&'static str, &str)]) {
+    let mut counters = REGISTRY.counters.lock().expect("Metrics lock poisoned");
+    *counters
+        .entry(name)
+        .or_default()
+        .entry(to_labels(labels))
+        .or_insert(0) += 1;
+}
+
+pub fn set_gauge(name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+    let mut gauges = REGISTRY.gauges.lock().expect("Metrics lock poisoned");
+    gauges
+        .entry(name)
+        .or_default()
+        .insert(to_labels(labels), value);
+}
+
+pub fn observe_duration(name: &'static str, labels: &[(&'static str, &str)], duration: Duration) {
+    let mut histograms = REGISTRY.histograms.lock().expect("Metrics lock poisoned");
+    histograms
+        .entry(name)
+        .or_default()
+        .entry(to_labels(labels))
+        .or_insert_with(Histogram::new)
+        .observe(duration.as_secs_f64());
+}
+
+/// Result of looking up a value through the cache
+pub enum CacheOutcome {
+    /// Served from the cache
+    Hit,
+    /// Served from the cache after it expired
+    Stale,
+    /// A cached upstream error
+    CachedError,
+    /// Not cached, fetched or generated successfully
+    Miss,
+    /// Not cached, fetching or generating it failed
+    Error,
+}
+
+impl CacheOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CacheOutcome::Hit => "hit",
+            CacheOutcome::Stale => "stale",
+            CacheOutcome::CachedError => "cached_error",
+            CacheOutcome::Miss => "miss",
+            CacheOutcome::Error => "error",
+        }
+    }
+}
+
+/// Records a cache lookup (`operation` being `request` or `response`) for a key family and chain
+pub fn record_cache_lookup(
+    operation: &str,
+    family: &str,
+    chain_id: &str,
+    outcome: CacheOutcome,
+    duration: Duration,
+) {
+    let labels = [
+        ("operation", operation),
+        ("family", family),
+        ("chain", chain_id),
+        ("outcome", outcome.as_str()),
+    ];
+    increment_counter("cache_lookups_total", &labels);
+    observe_duration("cache_lookup_duration_seconds", &labels, duration);
+}
+
+pub fn render() -> String {
+    let mut output = String::new();
+    for (name, series) in REGISTRY
+        .counters
+        .lock()
+        .expect("Metrics lock poisoned")
+        .iter()
+    {
+        writeln!(output, "# TYPE {} counter", name).unwrap();
+        for (labels, value) in series {
+            writeln!(output, "{}{} {}", name, format_labels(labels, None), value).unwrap();
+        }
+    }
+    for (name, series) in REGISTRY
+        .gauges
+        .lock()
+        .expect("Metrics lock poisoned")
+        .iter()
+    {
+        writeln!(output, "# TYPE {} gauge", name).unwrap();
+        for (labels, value) in series {
+            writeln!(output, "{}{} {}", name, format_labels(labels, None), value).unwrap();
+        }
+    }
+    for (name, series) in REGISTRY
+        .histograms
+        .lock()
+        .expect("Metrics lock poisoned")
+        .iter()
+    {
+        writeln!(output, "# TYPE {} histogram", name).unwrap();
+        for (labels, histogram) in series {
+            for (bucket, upper_bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS.iter()) {
+                let le = upper_bound.to_string();
+                let labels = format_labels(labels, Some(&le));
+                writeln!(output, "{}_bucket{} {}", name, labels, bucket).unwrap();
+            }
+            let labels_inf = format_labels(labels, Some("+Inf"));
+            let labels = format_labels(labels, None);
+            writeln!(output, "{}_bucket{} {}", name, labels_inf, histogram.count).unwrap();
+            writeln!(output, "{}_sum{} {}", name, labels, histogram.sum).unwrap();
+            writeln!(output, "{}_count{} {}", name, labels, histogram.count).unwrap();
+        }
+    }
+    output
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut formatted: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+        .collect();
+    if let Some(le) = le {
+        formatted.push(format!("le=\"{}\"", le));
+    }
+    if formatted.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", formatted.join(","))
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use crate::monitoring::metrics::{
+    increment_counter, record_cache_lookup, render, set_gauge, CacheOutcome,
+};
+
+#[test]
+fn render_counters() {
+    increment_counter("test_render_total", &[("kind", "a\"b")]);
+    increment_counter("test_render_total", &[("kind", "a\"b")]);
+
+    let actual = render();
+
+    assert!(actual.contains("# TYPE test_render_total counter\n"));
+    assert!(actual.contains("test_render_total{kind=\"a\\\"b\"} 2\n"));
+}
+
+#[test]
+fn render_cache_lookup_histogram() {
+    record_cache_lookup(
+        "request",
+        "test_family",
+        "4",
+        CacheOutcome::Hit,
+        Duration::from_millis(20),
+    );
+
+    let actual = render();
+    let labels = "operation=\"request\",family=\"test_family\",chain=\"4\",outcome=\"hit\"";
+
+    assert!(actual.contains(&format!("cache_lookups_total{{{}}} 1\n", labels)));
+    assert!(actual.contains(&format!(
+        "cache_lookup_duration_seconds_bucket{{{},le=\"0.01\"}} 0\n",
+        labels
+    )));
+    assert!(actual.contains(&format!(
+        "cache_lookup_duration_seconds_bucket{{{},le=\"0.025\"}} 1\n",
+        labels
+    )));
+    assert!(actual.contains(&format!(
+        "cache_lookup_duration_seconds_count{{{}}} 1\n",
+        labels
+    )));
+}
+
+#[test]
+fn render_gauges() {
+    set_gauge("test_render_gauge", &[("host", "example.com")], 1.0);
+    set_gauge("test_render_gauge", &[("host", "example.com")], 2.0);
+
+    let actual = render();
+
+    assert!(actual.contains("# TYPE test_render_gauge gauge\n"));
+    assert!(actual.contains("test_render_gauge{host=\"example.com\"} 2\n"));
+}
@@ -1 +1,2 @@
+mod metrics;
 mod path_patterns;
@@ -59,7 +59,8 @@ impl FiatInfoProvider {
     }
 
     async fn fetch_exchange(&self) -> ApiResult<Exchange> {
-        let url = base_exchange_api_uri();
+        let url =
+            base_exchange_api_uri().ok_or_else(|| api_error!("Exchange API not configured"))?;
         let body = RequestCached::new(url, &self.client, &self.cache)
             .cache_duration(exchange_api_cache_duration())
             .error_cache_duration(short_error_duration())
@@ -13,6 +13,7 @@ use serde::{Deserialize, Serialize};
 use serde_json;
 
 use crate::cache::cache_operations::RequestCached;
+use crate::cache::error_policy::ErrorStatus;
 use crate::cache::manager::ChainCache;
 use crate::cache::Cache;
 use crate::common::models::addresses::AddressEx;
@@ -20,13 +21,7 @@ use crate::common::models::backend::chains::ChainInfo;
 use crate::common::models::backend::safe_apps::SafeApp;
 use crate::common::models::backend::safes::MasterCopy;
 use crate::common::models::page::Page;
-use crate::config::{
-    address_info_cache_duration, chain_info_cache_duration, chain_info_request_timeout,
-    contract_info_request_timeout, default_request_timeout, long_error_duration,
-    request_cache_duration, safe_info_cache_duration, safe_info_request_timeout,
-    short_error_duration, token_cache_size_count, token_info_cache_duration,
-    token_info_request_timeout,
-};
+use crate::config::Config;
 use crate::providers::address_info::ContractInfo;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::{ApiError, ApiResult, ErrorDetails};
@@ -113,12 +108,18 @@ pub trait InfoProvider {
     fn chain_id(&self) -> &str;
     fn client(&self) -> Arc<dyn HttpClient>;
     fn cache(&self) -> Arc<dyn Cache>;
+    /// Timeouts and cache durations of the chain, see [crate::config::for_chain]
+    fn config(&self) -> Arc<Config>;
+    /// [RequestCached] of `url` for the chain, see [RequestCached::new_for_chain]
+    fn request(&self, url: String) -> RequestCached;
 }
 
 pub struct DefaultInfoProvider<'p> {
     pub chain_id: &'p str,
     client: Arc<dyn HttpClient>,
     cache: Arc<dyn Cache>,
+    // Timeouts and cache durations of the chain
+    config: Arc<Config>,
     // Mutex is an async Mutex, meaning that the lock is non-blocking
     safe_cache: Mutex<HashMap<String, Option<SafeInfo>>>,
     token_cache: Mutex<HashMap<String, Option<TokenInfo>>>,
@@ -158,9 +159,7 @@ impl InfoProvider for DefaultInfoProvider<'_> {
     async fn safe_app_info(&self, url: &str) -> ApiResult<SafeAppInfo> {
         let config_service_url = config_uri!("/v1/safe-apps/?url={}", url);
 
-        let result = RequestCached::new(config_service_url, &self.client, &self.cache)
-            .execute()
-            .await?;
+        let result = self.request(config_service_url).execute().await?;
 
         let config_safe_apps: Vec<SafeApp> = serde_json::from_str::<Vec<SafeApp>>(&result)?;
 
@@ -180,9 +179,7 @@ impl InfoProvider for DefaultInfoProvider<'_> {
 
     async fn safe_app_info_by_id(&self, id: u64) -> ApiResult<SafeAppInfo> {
         let config_service_url = config_uri!("/v1/safe-apps/");
-        let result = RequestCached::new(config_service_url, &self.client, &self.cache)
-            .execute()
-            .await?;
+        let result = self.request(config_service_url).execute().await?;
 
         let safe_apps: Vec<SafeApp> = serde_json::from_str::<Vec<SafeApp>>(&result)?;
         let safe_app: Option<&SafeApp> = safe_apps.iter().find(|safe_app| safe_app.id == id);
@@ -203,10 +200,15 @@ impl InfoProvider for DefaultInfoProvider<'_> {
 
     async fn contract_info(&self, contract_address: &str) -> ApiResult<ContractInfo> {
         let url = core_uri!(self, "/v1/contracts/{}/", contract_address)?;
-        let contract_info_json = RequestCached::new(url, &self.client, &self.cache)
-            .cache_duration(address_info_cache_duration())
-            .error_cache_duration(long_error_duration())
-            .request_timeout(contract_info_request_timeout())
+        let contract_info_json = self
+            .request(url)
+            .cache_duration(self.config.address_info_cache_duration)
+            .error_cache_duration(self.config.long_error_duration)
+            .cache_errors(
+                ErrorStatus::Code(404),
+                self.config.unknown_contract_cache_duration,
+            )
+            .request_timeout(self.config.contract_info_request_timeout)
             .execute()
             .await?;
 
@@ -244,6 +246,14 @@ impl InfoProvider for DefaultInfoProvider<'_> {
     fn cache(&self) -> Arc<dyn Cache> {
         self.cache.clone()
     }
+
+    fn config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    fn request(&self, url: String) -> RequestCached {
+        RequestCached::new_for_chain(url, &self.client, &self.cache, self.chain_id)
+    }
 }
 
 impl<'a> DefaultInfoProvider<'a> {
@@ -252,6 +262,7 @@ impl<'a> DefaultInfoProvider<'a> {
             chain_id,
             client: context.http_client(),
             cache: context.cache(ChainCache::from(chain_id)),
+            config: context.chain_config(chain_id),
             safe_cache: Default::default(),
             token_cache: Default::default(),
             chain_cache: Default::default(),
@@ -284,21 +295,26 @@ impl DefaultInfoProvider<'_> {
 
     async fn load_safe_info(&self, safe: String) -> ApiResult<Option<SafeInfo>> {
         let url = core_uri!(self, "/v1/safes/{}/", safe)?;
-        let data = RequestCached::new(url, &self.client, &self.cache)
-            .cache_duration(safe_info_cache_duration())
-            .error_cache_duration(short_error_duration())
-            .request_timeout(safe_info_request_timeout())
+        let data = self
+            .request(url)
+            .cache_duration(self.config.safe_info_cache_duration)
+            .error_cache_duration(self.config.short_error_duration)
+            .request_timeout(self.config.safe_info_request_timeout)
+            .stale_while_revalidate(self.config.stale_while_revalidate_duration)
+            .stale_if_error(self.config.stale_if_error_duration)
             .execute()
             .await?;
         Ok(serde_json::from_str(&data).ok())
     }
 
     async fn populate_token_cache(&self) -> ApiResult<()> {
-        let token_cache_size_count = token_cache_size_count();
+        let token_cache_size_count = self.config.token_cache_size_count;
         let url = core_uri!(self, "/v1/tokens/?limit={}", token_cache_size_count)?;
         let request = {
             let mut request = Request::new(url);
-            request.timeout(Duration::from_millis(token_info_request_timeout()));
+            request.timeout(Duration::from_millis(
+                self.config.token_info_request_timeout,
+            ));
             request
         };
 
@@ -313,7 +329,8 @@ impl DefaultInfoProvider<'_> {
         Ok(())
     }
 
-    async fn check_token_cache(&self) -> ApiResult<()> {
+    /// Populates the shared token cache of the chain, unless it is already present
+    pub async fn check_token_cache(&self) -> ApiResult<()> {
         let token_key = generate_token_key(&self.chain_id);
         if self.cache.has_key(&token_key).await {
             return Ok(());
@@ -324,14 +341,14 @@ impl DefaultInfoProvider<'_> {
         let result = self.populate_token_cache().await;
         if result.is_ok() {
             self.cache
-                .expire_entity(&token_key, token_info_cache_duration())
+                .expire_entity(&token_key, self.config.token_info_cache_duration)
                 .await;
             self.cache
                 .insert_in_hash(&token_key, "state", "populated")
                 .await;
         } else {
             self.cache
-                .expire_entity(&token_key, short_error_duration())
+                .expire_entity(&token_key, self.config.short_error_duration)
                 .await;
             self.cache
                 .insert_in_hash(&token_key, "state", "errored")
@@ -341,6 +358,10 @@ impl DefaultInfoProvider<'_> {
     }
 
     async fn load_token_info(&self, token: String) -> ApiResult<Option<TokenInfo>> {
+        // Without a shared cache the token list would be fetched again on every request
+        if !self.cache.is_available().await {
+            return self.load_single_token_info(&token).await;
+        }
         self.check_token_cache().await?;
         match self
             .cache
@@ -352,12 +373,29 @@ impl DefaultInfoProvider<'_> {
         }
     }
 
+    async fn load_single_token_info(&self, token: &str) -> ApiResult<Option<TokenInfo>> {
+        let url = core_uri!(self, "/v1/tokens/{}/", token)?;
+        let result = self
+            .request(url)
+            .cache_duration(self.config.token_info_cache_duration)
+            .error_cache_duration(self.config.long_error_duration)
+            .request_timeout(self.config.token_info_request_timeout)
+            .execute()
+            .await;
+        match result {
+            Ok(body) => Ok(serde_json::from_str::<TokenInfo>(&body).ok()),
+            Err(error) if error.status == 404 => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
     async fn load_chain_info(&self) -> ApiResult<Option<ChainInfo>> {
         let url = config_uri!("/v1/chains/{}/", self.chain_id);
-        let data = RequestCached::new(url, &self.client, &self.cache)
-            .cache_duration(chain_info_cache_duration())
-            .error_cache_duration(short_error_duration())
-            .request_timeout(chain_info_request_timeout())
+        let data = self
+            .request(url)
+            .cache_duration(self.config.chain_info_cache_duration)
+            .error_cache_duration(self.config.short_error_duration)
+            .request_timeout(self.config.chain_info_request_timeout)
             .execute()
             .await?;
         let result = serde_json::from_str::<ChainInfo>(&data).ok();
@@ -366,10 +404,11 @@ impl DefaultInfoProvider<'_> {
 
     pub async fn master_copies(&self) -> ApiResult<Vec<MasterCopy>> {
         let url = core_uri!(self, "/v1/about/master-copies/")?;
-        let body = RequestCached::new(url, &self.client, &self.cache)
-            .cache_duration(request_cache_duration())
-            .error_cache_duration(short_error_duration())
-            .request_timeout(default_request_timeout())
+        let body = self
+            .request(url)
+            .cache_duration(self.config.request_cache_duration)
+            .error_cache_duration(self.config.short_error_duration)
+            .request_timeout(self.config.default_request_timeout)
             .execute()
             .await?;
         Ok(serde_json::from_str(&body)?)
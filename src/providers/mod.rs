@@ -5,6 +5,8 @@ pub mod ext;
 pub mod fiat;
 #[doc(hidden)]
 pub mod info;
+#[doc(hidden)]
+pub mod warm_up;
 
 #[cfg(test)]
 mod tests;
@@ -1,2 +1,3 @@
 mod fiat;
 mod info;
+mod warm_up;
@@ -0,0 +1,208 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mockall::predicate::eq;
+
+use crate::cache::manager::ChainCache;
+use crate::cache::CACHE_LOCK_PREFIX;
+use crate::common::models::page::{Page, PageMetadata};
+use crate::config::{
+    chain_info_request_timeout, current, token_info_request_timeout, with_config, Config,
+};
+use crate::providers::info::{generate_token_key, TokenInfo};
+use crate::providers::warm_up::{parse_list, warm_up_caches, warm_up_chain};
+use crate::utils::context::RequestContext;
+use crate::utils::errors::ApiError;
+use crate::utils::http_client::{HttpClient, MockHttpClient, Request, Response};
+use crate::{create_cache_manager, RedisCacheManager};
+
+fn expect_request(mock_http_client: &mut MockHttpClient, request: Request, body: String) {
+    mock_http_client
+        .expect_get()
+        .times(1)
+        .with(eq(request))
+        .return_once(move |_| {
+            Ok(Response {
+                status_code: 200,
+                body,
+            })
+        });
+}
+
+#[test]
+fn parse_list_skips_empty_entries() {
+    assert_eq!(parse_list(" 1, 137,,4 "), vec!["1", "137", "4"]);
+    assert!(parse_list("").is_empty());
+}
+
+#[rocket::async_test]
+async fn warm_up_chain_fills_caches() {
+    let mut mock_http_client = MockHttpClient::new();
+    let mut chain_request = Request::new(config_uri!("/v1/chains/{}/", 4));
+    chain_request.timeout(Duration::from_millis(chain_info_request_timeout()));
+    expect_request(
+        &mut mock_http_client,
+        chain_request,
+        String::from(crate::tests::json::CHAIN_INFO_RINKEBY),
+    );
+    expect_request(
+        &mut mock_http_client,
+        Request::new(config_uri!(
+            "/v1/safe-apps/?chainId={}&clientUrl={}&url={}",
+            4,
+            "https://app.safe.global",
+            ""
+        )),
+        String::from("[]"),
+    );
+    expect_request(
+        &mut mock_http_client,
+        Request::new(String::from(
+            "https://safe-transaction.rinkeby.staging.gnosisdev.com/api/v1/about/master-copies/",
+        )),
+        String::from("[]"),
+    );
+    let mut token_request = Request::new(String::from(
+        "https://safe-transaction.rinkeby.staging.gnosisdev.com/api/v1/tokens/?limit=20000",
+    ));
+    token_request.timeout(Duration::from_millis(token_info_request_timeout()));
+    let page_tokens: Page<TokenInfo> = Page {
+        next: None,
+        previous: None,
+        results: vec![serde_json::from_str(crate::tests::json::TOKEN_BAT).unwrap()],
+    };
+    expect_request(
+        &mut mock_http_client,
+        token_request,
+        serde_json::to_string(&page_tokens).unwrap(),
+    );
+    let context = RequestContext::setup_for_test(
+        String::from("cache_warm_up"),
+        config_uri!(""),
+        &(Arc::new(mock_http_client) as Arc<dyn HttpClient>),
+        &(Arc::new(create_cache_manager().await) as Arc<dyn RedisCacheManager>),
+    )
+    .await;
+
+    warm_up_chain(&context, "4").await.unwrap();
+
+    assert!(
+        context
+            .cache(ChainCache::from("4"))
+            .has_key(&generate_token_key("4"))
+            .await
+    );
+    // Everything is served from the caches now, so no more upstream requests are expected
+    warm_up_chain(&context, "4").await.unwrap();
+}
+
+#[rocket::async_test]
+async fn warm_up_chain_goes_on_after_a_failed_step() {
+    let mut mock_http_client = MockHttpClient::new();
+    let mut chain_request = Request::new(config_uri!("/v1/chains/{}/", 5));
+    chain_request.timeout(Duration::from_millis(chain_info_request_timeout()));
+    expect_request(
+        &mut mock_http_client,
+        chain_request,
+        String::from(crate::tests::json::CHAIN_INFO_RINKEBY),
+    );
+    mock_http_client
+        .expect_get()
+        .times(1)
+        .with(eq(Request::new(config_uri!(
+            "/v1/safe-apps/?chainId={}&clientUrl={}&url={}",
+            5,
+            "https://app.safe.global",
+            ""
+        ))))
+        .return_once(move |_| {
+            Err(ApiError::from_http_response(&Response {
+                status_code: 500,
+                body: String::from("Internal server error"),
+            }))
+        });
+    // Shared with the other chains of the transaction service, so it may already be cached
+    mock_http_client
+        .expect_get()
+        .times(0..2)
+        .with(eq(Request::new(String::from(
+            "https://safe-transaction.rinkeby.staging.gnosisdev.com/api/v1/about/master-copies/",
+        ))))
+        .returning(move |_| {
+            Ok(Response {
+                status_code: 200,
+                body: String::from("[]"),
+            })
+        });
+    let mut token_request = Request::new(String::from(
+        "https://safe-transaction.rinkeby.staging.gnosisdev.com/api/v1/tokens/?limit=20000",
+    ));
+    token_request.timeout(Duration::from_millis(token_info_request_timeout()));
+    let page_tokens: Page<TokenInfo> = Page {
+        next: None,
+        previous: None,
+        results: vec![serde_json::from_str(crate::tests::json::TOKEN_BAT).unwrap()],
+    };
+    expect_request(
+        &mut mock_http_client,
+        token_request,
+        serde_json::to_string(&page_tokens).unwrap(),
+    );
+    let context = RequestContext::setup_for_test(
+        String::from("cache_warm_up"),
+        config_uri!(""),
+        &(Arc::new(mock_http_client) as Arc<dyn HttpClient>),
+        &(Arc::new(create_cache_manager().await) as Arc<dyn RedisCacheManager>),
+    )
+    .await;
+
+    let actual = warm_up_chain(&context, "5").await;
+
+    assert_eq!(
+        actual.unwrap_err().details.message.unwrap(),
+        "Warming up the safe apps of https://app.safe.global failed"
+    );
+    assert!(
+        context
+            .cache(ChainCache::from("5"))
+            .has_key(&generate_token_key("5"))
+            .await
+    );
+}
+
+#[rocket::async_test]
+async fn warm_up_caches_skips_chains_locked_in_their_cache() {
+    let mut mock_http_client = MockHttpClient::new();
+    let mut chains_request = Request::new(config_uri!(
+        "/v1/chains/?{}",
+        PageMetadata::default().to_url_string()
+    ));
+    chains_request.timeout(Duration::from_millis(chain_info_request_timeout()));
+    // Only the chain list is requested, if not cached yet
+    mock_http_client
+        .expect_get()
+        .times(0..2)
+        .with(eq(chains_request))
+        .returning(move |_| {
+            Ok(Response {
+                status_code: 200,
+                body: String::from("{\"next\":null,\"previous\":null,\"results\":[]}"),
+            })
+        });
+    let context = RequestContext::setup_for_test(
+        String::from("cache_warm_up"),
+        config_uri!(""),
+        &(Arc::new(mock_http_client) as Arc<dyn HttpClient>),
+        &(Arc::new(create_cache_manager().await) as Arc<dyn RedisCacheManager>),
+    )
+    .await;
+    let mut config = Config::clone(&current());
+    config.cache_warm_up_chains = String::from("6");
+    let cache = context.cache(ChainCache::from("6"));
+    let lock_key = format!("{}_cache_warm_up_6", CACHE_LOCK_PREFIX);
+    assert!(cache.try_lock(&lock_key, "other_instance", 60 * 1000).await);
+
+    with_config(config, warm_up_caches(&context)).await;
+
+    cache.unlock(&lock_key, "other_instance").await;
+}
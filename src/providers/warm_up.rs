@@ -0,0 +1,170 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::tokio::time::sleep;
+use rocket::{Orbit, Rocket};
+
+use crate::cache::manager::{ChainCache, RedisCacheManager};
+use crate::cache::{Cache, CACHE_LOCK_PREFIX};
+use crate::config::{
+    self, cache_warm_up_chains, cache_warm_up_client_urls, cache_warm_up_enabled,
+    cache_warm_up_interval,
+};
+use crate::providers::info::{DefaultInfoProvider, InfoProvider};
+use crate::routes::chains::handlers::get_chains_paginated;
+use crate::routes::safe_apps::handlers::safe_apps;
+use crate::utils::context::RequestContext;
+use crate::utils::errors::{ApiError, ApiResult};
+use crate::utils::http_client::HttpClient;
+
+// Upper bound of a warm-up, after which another one may start even if it did not finish
+const WARM_UP_LOCK_TIMEOUT: usize = 5 * 60 * 1000;
+
+/// Warms up the caches once the server is up, and then every [cache_warm_up_interval]
+pub struct CacheWarmUp();
+
+#[rocket::async_trait]
+impl Fairing for CacheWarmUp {
+    fn info(&self) -> Info {
+        Info {
+            name: "Warm up caches",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        if !cache_warm_up_enabled() {
+            return;
+        }
+        let (http_client, cache_manager) = match (
+            rocket.state::<Arc<dyn HttpClient>>(),
+            rocket.state::<Arc<dyn RedisCacheManager>>(),
+        ) {
+            (Some(http_client), Some(cache_manager)) => {
+                (http_client.clone(), cache_manager.clone())
+            }
+            _ => return,
+        };
+        let context = RequestContext::background("cache_warm_up", http_client, cache_manager);
+        rocket::tokio::spawn(async move {
+            loop {
+                warm_up_caches(&context).await;
+                let interval = cache_warm_up_interval();
+                if interval == 0 {
+                    break;
+                }
+                sleep(Duration::from_millis(interval)).await;
+            }
+        });
+    }
+}
+
+/// Warms up the caches in the background, if enabled, e.g. right after they were flushed
+pub fn spawn_warm_up(context: RequestContext) {
+    if cache_warm_up_enabled() {
+        config::spawn(async move { warm_up_caches(&context).await });
+    }
+}
+
+/// Prefetches the data most requests depend on, through the same paths as the requests
+/// themselves so that they share the cache entries:
+/// - the chain list, and the configuration of each chain in [cache_warm_up_chains]
+/// - the safe apps of these chains for each of [cache_warm_up_client_urls], and their supported
+///   master copies
+/// - their token list (see [DefaultInfoProvider::check_token_cache])
+///
+/// The chain list and each chain are locked in the cache backend serving them (see
+/// `CACHE_ROUTES`), and skipped while another warm-up holds the lock, in this or another instance.
+pub async fn warm_up_caches(context: &RequestContext) {
+    let started = Instant::now();
+    let lock_key = format!("{}_cache_warm_up", CACHE_LOCK_PREFIX);
+    exclusively(&context.cache(ChainCache::Other), &lock_key, async {
+        if let Err(error) = get_chains_paginated(context, &None).await {
+            log::warn!("Warming up the chain list failed: {}", error);
+        }
+    })
+    .await;
+    for chain_id in parse_list(&cache_warm_up_chains()) {
+        let lock_key = format!("{}_cache_warm_up_{}", CACHE_LOCK_PREFIX, chain_id);
+        let cache = context.cache(ChainCache::from(chain_id.as_str()));
+        exclusively(&cache, &lock_key, async {
+            if let Err(error) = warm_up_chain(context, &chain_id).await {
+                log::warn!("Caches of chain {} partly warmed up: {}", chain_id, error);
+            }
+        })
+        .await;
+    }
+    log::info!("Caches warmed up in {}ms", started.elapsed().as_millis());
+}
+
+// Runs `warm_up` unless another one holding `lock_key` in `cache` is running
+async fn exclusively(cache: &Arc<dyn Cache>, lock_key: &str, warm_up: impl Future<Output = ()>) {
+    let lock_token = format!("{:016x}", rand::random::<u64>());
+    if !cache
+        .try_lock(lock_key, &lock_token, WARM_UP_LOCK_TIMEOUT)
+        .await
+    {
+        log::info!("Skipping {}, another warm-up holds it", lock_key);
+        return;
+    }
+    warm_up.await;
+    cache.unlock(lock_key, &lock_token).await;
+}
+
+/// Warms up every step for `chain_id`, even once one failed. Each failure is logged, and the
+/// steps which failed are reported in the error.
+pub(super) async fn warm_up_chain(context: &RequestContext, chain_id: &str) -> ApiResult<()> {
+    let info_provider = DefaultInfoProvider::new(chain_id, context);
+    let mut failed = vec![];
+    let mut check = |step: String, result: ApiResult<()>| {
+        if let Err(error) = result {
+            log::warn!(
+                "Warming up {} of chain {} failed: {}",
+                step,
+                chain_id,
+                error
+            );
+            failed.push(step);
+        }
+    };
+    check(
+        String::from("the chain info"),
+        info_provider.chain_info().await.map(drop),
+    );
+    let chain_id = chain_id.to_string();
+    for client_url in parse_list(&cache_warm_up_client_urls()) {
+        let result = safe_apps(context, &chain_id, &Some(client_url.clone()), &None).await;
+        check(format!("the safe apps of {}", client_url), result.map(drop));
+    }
+    check(
+        String::from("the master copies"),
+        info_provider.master_copies().await.map(drop),
+    );
+    // Without a shared cache there is no token list to keep
+    if info_provider.cache().is_available().await {
+        check(
+            String::from("the token list"),
+            info_provider.check_token_cache().await,
+        );
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::new_from_message(format!(
+            "Warming up {} failed",
+            failed.join(", ")
+        )))
+    }
+}
+
+/// Values of a comma separated setting, e.g. [cache_warm_up_chains]
+pub(crate) fn parse_list(values: &str) -> Vec<String> {
+    values
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -3,7 +3,8 @@ use rocket::response::content;
 use crate::cache::cache_operations::CacheResponse;
 use crate::cache::manager::ChainCache;
 use crate::common::routes::authorization::AuthorizationToken;
-use crate::config::about_cache_duration;
+use crate::config::{self, about_cache_duration};
+use crate::monitoring::metrics;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::about::handlers;
 use crate::utils::context::RequestContext;
@@ -33,6 +34,7 @@ pub async fn get_chains_about(
 ) -> ApiResult<content::RawJson<String>> {
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
         .duration(about_cache_duration())
+        .public()
         .resp_generator(|| handlers::chains_about(&context, &chain_id))
         .execute()
         .await
@@ -85,6 +87,7 @@ pub async fn get_master_copies(
 ) -> ApiResult<content::RawJson<String>> {
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
         .duration(about_cache_duration())
+        .public()
         .resp_generator(|| handlers::get_master_copies(&context, chain_id.as_str()))
         .execute()
         .await
@@ -104,6 +107,12 @@ pub async fn backbone(
     Ok(content::RawJson(client.get(request).await?.body))
 }
 
+#[doc(hidden)]
+#[get("/about/metrics")]
+pub async fn get_metrics(_token: AuthorizationToken) -> String {
+    metrics::render()
+}
+
 #[doc(hidden)]
 #[get("/about/redis")]
 pub async fn redis(context: RequestContext, _token: AuthorizationToken) -> ApiResult<String> {
@@ -113,3 +122,11 @@ pub async fn redis(context: RequestContext, _token: AuthorizationToken) -> ApiRe
         .await
         .unwrap_or(String::new()))
 }
+
+#[doc(hidden)]
+#[get("/about/config")]
+pub async fn get_config(_token: AuthorizationToken) -> ApiResult<content::RawJson<String>> {
+    Ok(content::RawJson(
+        serde_json::to_string(&*config::current())?,
+    ))
+}
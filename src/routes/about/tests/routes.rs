@@ -1,15 +1,14 @@
 use core::time::Duration;
-use std::env;
 
 use mockall::predicate::eq;
 use rocket::http::{Header, Status};
 use rocket::local::asynchronous::Client;
 
 use crate::cache::MockCache;
-use crate::config::{build_number, chain_info_request_timeout, version};
+use crate::config::{build_number, chain_info_request_timeout, version, with_config};
 use crate::routes::about::models::{About, ChainAbout};
 use crate::routes::safes::models::Implementation;
-use crate::tests::main::{setup_rocket, setup_rocket_with_mock_cache};
+use crate::tests::main::{config_with_webhook_token, setup_rocket, setup_rocket_with_mock_cache};
 use crate::utils::http_client::{MockHttpClient, Request, Response};
 
 #[rocket::async_test]
@@ -210,7 +209,6 @@ async fn get_backbone() {
 #[rocket::async_test]
 #[ignore] // TODO remove this
 async fn get_redis() {
-    env::set_var("WEBHOOK_TOKEN", "test_webhook_token");
     let mock_http_client = {
         let mut mock_http_client = MockHttpClient::new();
         mock_http_client.expect_get().times(0);
@@ -236,7 +234,11 @@ async fn get_redis() {
         let mut response = client.get("/about/redis");
         response.add_header(Header::new("Host", "test.safe.global"));
         response.add_header(Header::new("Authorization", "Basic test_webhook_token"));
-        response.dispatch().await
+        with_config(
+            config_with_webhook_token("test_webhook_token"),
+            response.dispatch(),
+        )
+        .await
     };
 
     let expected = "Cache info";
@@ -244,3 +246,39 @@ async fn get_redis() {
     assert_eq!(response.status(), Status::Ok);
     assert_eq!(response.into_string().await.unwrap(), expected);
 }
+
+#[rocket::async_test]
+async fn get_config_redacts_secrets() {
+    let mock_http_client = {
+        let mut mock_http_client = MockHttpClient::new();
+        mock_http_client.expect_get().times(0);
+        mock_http_client
+    };
+
+    let client = Client::tracked(
+        setup_rocket(mock_http_client, routes![super::super::routes::get_config]).await,
+    )
+    .await
+    .expect("valid rocket instance");
+    let response = {
+        let mut response = client.get("/about/config");
+        response.add_header(Header::new("Host", "test.safe.global"));
+        response.add_header(Header::new("Authorization", "Basic test_webhook_token"));
+        with_config(
+            config_with_webhook_token("test_webhook_token"),
+            response.dispatch(),
+        )
+        .await
+    };
+
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_string().await.unwrap();
+    let config: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(config["webhook_token"], "<redacted>");
+    assert_eq!(config["redis_uri"], "<redacted>");
+    assert_eq!(
+        config["chain_info_request_timeout"],
+        chain_info_request_timeout()
+    );
+    assert!(!body.contains("test_webhook_token"));
+}
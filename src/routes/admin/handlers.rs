@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use serde_json::json;
+
+use crate::cache::cache_operations::{decode_cached_value, Invalidate, InvalidationPattern};
+use crate::config;
+use crate::routes::admin::models::{CacheEntry, CachedKey, ConfigReload, FlushDryRun};
+use crate::utils::context::RequestContext;
+use crate::utils::errors::{ApiError, ApiResult};
+
+const FLUSH_DRY_RUN_LIMIT: usize = 10000;
+
+// Several cache backends may be configured with the same Redis instance, so keys are reported once
+pub async fn cached_keys(
+    context: &RequestContext,
+    pattern: &InvalidationPattern,
+    limit: usize,
+) -> Vec<CachedKey> {
+    let mut seen = HashSet::new();
+    let mut cached_keys = vec![];
+    for cache in context.all_caches() {
+        let keys = Invalidate::new(pattern.clone(), cache.clone())
+            .matching_keys(limit)
+            .await;
+        for key in keys {
+            if cached_keys.len() == limit {
+                return cached_keys;
+            }
+            if seen.insert(key.to_string()) {
+                let ttl = cache.ttl(&key).await;
+                cached_keys.push(CachedKey { key, ttl });
+            }
+        }
+    }
+    cached_keys
+}
+
+pub async fn cache_entry(context: &RequestContext, key: &str) -> ApiResult<CacheEntry> {
+    for cache in context.all_caches() {
+        if let Some(value) = cache.fetch_bytes(key).await {
+            let (status_code, body) = decode_cached_value(key, value);
+            return Ok(CacheEntry {
+                key: key.to_string(),
+                ttl: cache.ttl(key).await,
+                status_code,
+                body,
+            });
+        }
+    }
+    Err(client_error!(404, "No cache entry for the given key"))
+}
+
+// The keys are held in memory to be counted once across caches, so counting stops past a limit
+pub async fn flush_dry_run(context: &RequestContext, pattern: &InvalidationPattern) -> FlushDryRun {
+    let mut keys = HashSet::new();
+    for cache in context.all_caches() {
+        keys.extend(
+            Invalidate::new(pattern.clone(), cache)
+                .matching_keys(FLUSH_DRY_RUN_LIMIT + 1)
+                .await,
+        );
+    }
+    FlushDryRun {
+        count: keys.len().min(FLUSH_DRY_RUN_LIMIT),
+        truncated: keys.len() > FLUSH_DRY_RUN_LIMIT,
+    }
+}
+
+pub fn reload_config() -> ApiResult<ConfigReload> {
+    let restart_required = config::reload().map_err(|error| {
+        ApiError::new_from_message_with_debug("Invalid configuration", Some(json!(error.0)))
+    })?;
+    Ok(ConfigReload {
+        restart_required: restart_required.into_iter().map(String::from).collect(),
+    })
+}
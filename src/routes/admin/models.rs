@@ -0,0 +1,35 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedKey {
+    pub key: String,
+    /// Remaining time to live in milliseconds, if the key expires
+    pub ttl: Option<usize>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    pub key: String,
+    pub ttl: Option<usize>,
+    /// Status code of the upstream response, only available for cached requests
+    pub status_code: Option<u16>,
+    pub body: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FlushDryRun {
+    /// Number of keys the flush would delete
+    pub count: usize,
+    /// Whether counting stopped early, the flush then deletes more than `count` keys
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReload {
+    /// Keys whose new value only applies after a restart
+    pub restart_required: Vec<String>,
+}
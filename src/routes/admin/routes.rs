@@ -0,0 +1,65 @@
+use rocket::response::content;
+use rocket::serde::json::Json;
+
+use crate::cache::cache_operations::InvalidationPattern;
+use crate::common::routes::authorization::AuthorizationToken;
+use crate::routes::admin::handlers;
+use crate::utils::context::RequestContext;
+use crate::utils::errors::ApiResult;
+
+const DEFAULT_KEYS_LIMIT: usize = 100;
+
+/// Lists the keys matching `invalidation_pattern` in every cache, along with their TTLs
+#[post(
+    "/v1/admin/cache/keys?<limit>",
+    format = "json",
+    data = "<invalidation_pattern>"
+)]
+pub async fn post_cache_keys(
+    context: RequestContext,
+    _token: AuthorizationToken,
+    limit: Option<usize>,
+    invalidation_pattern: Json<InvalidationPattern>,
+) -> ApiResult<content::RawJson<String>> {
+    let keys = handlers::cached_keys(
+        &context,
+        &invalidation_pattern.0,
+        limit.unwrap_or(DEFAULT_KEYS_LIMIT),
+    )
+    .await;
+    Ok(content::RawJson(serde_json::to_string(&keys)?))
+}
+
+/// Shows the entry stored under `key`, split into status code and body for cached requests
+#[get("/v1/admin/cache/entry?<key>")]
+pub async fn get_cache_entry(
+    context: RequestContext,
+    _token: AuthorizationToken,
+    key: String,
+) -> ApiResult<content::RawJson<String>> {
+    let entry = handlers::cache_entry(&context, &key).await?;
+    Ok(content::RawJson(serde_json::to_string(&entry)?))
+}
+
+/// Counts the keys a flush of `invalidation_pattern` would delete, without deleting them
+#[post(
+    "/v1/admin/cache/flush/dry-run",
+    format = "json",
+    data = "<invalidation_pattern>"
+)]
+pub async fn post_flush_dry_run(
+    context: RequestContext,
+    _token: AuthorizationToken,
+    invalidation_pattern: Json<InvalidationPattern>,
+) -> ApiResult<content::RawJson<String>> {
+    let dry_run = handlers::flush_dry_run(&context, &invalidation_pattern.0).await;
+    Ok(content::RawJson(serde_json::to_string(&dry_run)?))
+}
+
+/// Reloads the configuration without restarting, as a `SIGHUP` would. Invalid configurations are
+/// rejected, keeping the current one.
+#[post("/v1/admin/config/reload")]
+pub async fn post_config_reload(_token: AuthorizationToken) -> ApiResult<content::RawJson<String>> {
+    let reload = handlers::reload_config()?;
+    Ok(content::RawJson(serde_json::to_string(&reload)?))
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::{Client, LocalRequest, LocalResponse};
+use rocket::serde::json::json;
+use rocket::Route;
+use serde_json::Value;
+
+use crate::cache::manager::ChainCache;
+use crate::cache::Cache;
+use crate::config::with_config;
+use crate::tests::main::{config_with_webhook_token, setup_rocket};
+use crate::utils::http_client::MockHttpClient;
+use crate::RedisCacheManager;
+
+const SAFE_ADDRESS: &str = "0x4cb09344de5bCCD45F045c5Defa0E0452869FF0f";
+
+async fn setup_client(routes: Vec<Route>) -> (Client, Arc<dyn Cache>) {
+    let rocket = setup_rocket(MockHttpClient::new(), routes).await;
+    let cache = rocket
+        .state::<Arc<dyn RedisCacheManager>>()
+        .expect("RedisCacheManager unavailable")
+        .cache_for_chain(ChainCache::Other);
+    let client = Client::tracked(rocket)
+        .await
+        .expect("valid rocket instance");
+    (client, cache)
+}
+
+async fn dispatch_with_token(request: LocalRequest<'_>) -> LocalResponse<'_> {
+    with_config(
+        config_with_webhook_token("test_webhook_token"),
+        request.dispatch(),
+    )
+    .await
+}
+
+async fn populate(cache: &Arc<dyn Cache>) {
+    cache
+        .create(
+            &format!("c_resp_/v1/chains/1/safes/{}/balances/usd", SAFE_ADDRESS),
+            "{}",
+            60 * 1000,
+        )
+        .await;
+    cache
+        .create(
+            &format!(
+                "c_reqs_https://tx.service/api/v1/safes/{}/balances/usd/",
+                SAFE_ADDRESS
+            ),
+            "404;Not found",
+            60 * 1000,
+        )
+        .await;
+}
+
+#[rocket::async_test]
+async fn post_cache_keys_no_token() {
+    let (client, _) = setup_client(routes![super::super::routes::post_cache_keys]).await;
+
+    let response = client
+        .post("/v1/admin/cache/keys")
+        .body(&json!({"invalidate": "Chains"}).to_string())
+        .header(ContentType::JSON)
+        .header(Header::new("Host", "test.safe.global"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn post_cache_keys_lists_keys_with_ttl() {
+    let (client, cache) = setup_client(routes![super::super::routes::post_cache_keys]).await;
+    populate(&cache).await;
+
+    let response = dispatch_with_token(
+        client
+            .post("/v1/admin/cache/keys?limit=10")
+            .body(
+                &json!({"invalidate": "Balances", "pattern_details": ["Responses", SAFE_ADDRESS]})
+                    .to_string(),
+            )
+            .header(ContentType::JSON)
+            .header(Header::new("Host", "test.safe.global"))
+            .header(Header::new("Authorization", "Basic test_webhook_token")),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let keys: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+    let keys = keys.as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(
+        keys[0]["key"],
+        format!("c_resp_/v1/chains/1/safes/{}/balances/usd", SAFE_ADDRESS)
+    );
+    assert!(keys[0]["ttl"].as_u64().unwrap() > 0);
+}
+
+#[rocket::async_test]
+async fn get_cache_entry_decodes_status_code() {
+    let (client, cache) = setup_client(routes![super::super::routes::get_cache_entry]).await;
+    populate(&cache).await;
+    let key = format!(
+        "c_reqs_https://tx.service/api/v1/safes/{}/balances/usd/",
+        SAFE_ADDRESS
+    );
+
+    let response = dispatch_with_token(
+        client
+            .get(format!(
+                "/v1/admin/cache/entry?key={}",
+                key.replace(':', "%3A").replace('/', "%2F")
+            ))
+            .header(Header::new("Host", "test.safe.global"))
+            .header(Header::new("Authorization", "Basic test_webhook_token")),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let entry: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+    assert_eq!(entry["key"], key);
+    assert_eq!(entry["statusCode"], 404);
+    assert_eq!(entry["body"], "Not found");
+}
+
+#[rocket::async_test]
+async fn get_cache_entry_missing_key() {
+    let (client, _) = setup_client(routes![super::super::routes::get_cache_entry]).await;
+
+    let response = dispatch_with_token(
+        client
+            .get("/v1/admin/cache/entry?key=c_resp_missing")
+            .header(Header::new("Host", "test.safe.global"))
+            .header(Header::new("Authorization", "Basic test_webhook_token")),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn post_flush_dry_run_keeps_keys() {
+    let (client, cache) = setup_client(routes![super::super::routes::post_flush_dry_run]).await;
+    populate(&cache).await;
+
+    let response = dispatch_with_token(
+        client
+            .post("/v1/admin/cache/flush/dry-run")
+            .body(
+                &json!({"invalidate": "Balances", "pattern_details": ["Both", SAFE_ADDRESS]})
+                    .to_string(),
+            )
+            .header(ContentType::JSON)
+            .header(Header::new("Host", "test.safe.global"))
+            .header(Header::new("Authorization", "Basic test_webhook_token")),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().await.unwrap(),
+        "{\"count\":2,\"truncated\":false}"
+    );
+    assert!(
+        cache
+            .has_key(&format!(
+                "c_resp_/v1/chains/1/safes/{}/balances/usd",
+                SAFE_ADDRESS
+            ))
+            .await
+    );
+}
+
+#[rocket::async_test]
+async fn post_config_reload_no_token() {
+    let (client, _) = setup_client(routes![super::super::routes::post_config_reload]).await;
+
+    let response = client
+        .post("/v1/admin/config/reload")
+        .header(Header::new("Host", "test.safe.global"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
@@ -2,7 +2,6 @@ use crate::cache::cache_operations::RequestCached;
 use crate::cache::manager::ChainCache;
 use crate::common::models::backend::balances::Balance as BalanceDto;
 use crate::common::models::backend::chains::NativeCurrency;
-use crate::config::{balances_cache_duration, balances_request_timeout};
 use crate::providers::fiat::FiatInfoProvider;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::balances::models::{Balance, Balances};
@@ -20,6 +19,7 @@ pub async fn balances(
     exclude_spam: bool,
 ) -> ApiResult<Balances> {
     let info_provider = DefaultInfoProvider::new(chain_id, &context);
+    let config = info_provider.config();
     let fiat_info_provider = FiatInfoProvider::new(&context);
     let url = core_uri!(
         info_provider,
@@ -30,8 +30,10 @@ pub async fn balances(
     )?;
 
     let body = RequestCached::new_from_context(url, context, ChainCache::from(chain_id))
-        .cache_duration(balances_cache_duration())
-        .request_timeout(balances_request_timeout())
+        .cache_duration(config.balances_cache_duration)
+        .request_timeout(config.balances_request_timeout)
+        .stale_while_revalidate(config.stale_while_revalidate_duration)
+        .stale_if_error(config.stale_if_error_duration)
         .execute()
         .await?;
     let backend_balances: Vec<BalanceDto> = serde_json::from_str(&body)?;
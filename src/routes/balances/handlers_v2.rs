@@ -10,10 +10,7 @@ use crate::common::models::backend::balances_v2::{
     Balance as BalanceDto, TokenPrice as BackendTokenPrice,
 };
 use crate::common::models::backend::chains::NativeCurrency;
-use crate::config::{
-    balances_core_request_cache_duration, balances_request_timeout,
-    concurrent_balance_token_requests, token_price_cache_duration,
-};
+use crate::config::concurrent_balance_token_requests;
 use crate::providers::fiat::FiatInfoProvider;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::balances::models::{Balance, Balances, TokenPrice};
@@ -29,6 +26,7 @@ pub async fn balances(
     exclude_spam: bool,
 ) -> ApiResult<Balances> {
     let info_provider = DefaultInfoProvider::new(chain_id, context);
+    let config = info_provider.config();
     let fiat_info_provider = FiatInfoProvider::new(context);
     let url = core_uri!(
         info_provider,
@@ -39,8 +37,10 @@ pub async fn balances(
     )?;
 
     let body = RequestCached::new_from_context(url, context, ChainCache::from(chain_id))
-        .cache_duration(balances_core_request_cache_duration())
-        .request_timeout(balances_request_timeout())
+        .cache_duration(config.balances_core_request_cache_duration)
+        .request_timeout(config.balances_request_timeout)
+        .stale_while_revalidate(config.stale_while_revalidate_duration)
+        .stale_if_error(config.stale_if_error_duration)
         .execute()
         .await?;
     let backend_balances: Vec<BalanceDto> = serde_json::from_str(&body)?;
@@ -138,7 +138,7 @@ async fn get_token_usd_rate(
 
     let body =
         RequestCached::new_from_context(url, context, ChainCache::from(info_provider.chain_id()))
-            .cache_duration(token_price_cache_duration())
+            .cache_duration(info_provider.config().token_price_cache_duration)
             .execute()
             .await?;
     let response: BackendTokenPrice = serde_json::from_str(&body)?;
@@ -1,10 +1,12 @@
-use rocket::futures::FutureExt;
+use std::sync::Arc;
+
 use rocket::response::content;
 
 use crate::cache::cache_operations::CacheResponse;
 use crate::cache::manager::ChainCache;
-use crate::config::{balances_cache_duration, feature_flag_balances_rate_implementation};
+use crate::config::feature_flag_balances_rate_implementation;
 use crate::routes::balances::handlers::fiat_codes;
+use crate::routes::balances::models::Balances;
 use crate::routes::balances::{handlers, handlers_v2};
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
@@ -39,35 +41,61 @@ pub async fn get_balances(
     trusted: Option<bool>,
     exclude_spam: Option<bool>,
 ) -> ApiResult<content::RawJson<String>> {
+    let config = context.chain_config(&chain_id);
+    let trusted = trusted.unwrap_or(false);
+    let exclude_spam = exclude_spam.unwrap_or(true);
+    let background = Arc::new(context.detach(&context.request_id));
+    let background_args = (chain_id.clone(), safe_address.clone(), fiat.clone());
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
-        .duration(balances_cache_duration())
-        .resp_generator(|| {
-            if feature_flag_balances_rate_implementation() {
-                handlers_v2::balances(
-                    &context,
-                    chain_id.as_str(),
-                    safe_address.as_str(),
-                    fiat.as_str(),
-                    trusted.unwrap_or(false),
-                    exclude_spam.unwrap_or(true),
-                )
-                .left_future()
-            } else {
-                handlers::balances(
+        .duration(config.balances_cache_duration)
+        .stale_while_revalidate(config.stale_while_revalidate_duration, move || {
+            let context = background.clone();
+            let (chain_id, safe_address, fiat) = background_args.clone();
+            async move {
+                balances(
                     &context,
-                    chain_id.as_str(),
-                    safe_address.as_str(),
-                    fiat.as_str(),
-                    trusted.unwrap_or(false),
-                    exclude_spam.unwrap_or(true),
+                    &chain_id,
+                    &safe_address,
+                    &fiat,
+                    trusted,
+                    exclude_spam,
                 )
-                .right_future()
+                .await
             }
         })
+        .stale_if_error(config.stale_if_error_duration)
+        .query_default("trusted", "false")
+        .query_default("exclude_spam", "true")
+        .resp_generator(|| {
+            balances(
+                &context,
+                &chain_id,
+                &safe_address,
+                &fiat,
+                trusted,
+                exclude_spam,
+            )
+        })
         .execute()
         .await
 }
 
+// Balances from the implementation selected by the feature flag
+async fn balances(
+    context: &RequestContext,
+    chain_id: &str,
+    safe_address: &str,
+    fiat: &str,
+    trusted: bool,
+    exclude_spam: bool,
+) -> ApiResult<Balances> {
+    if feature_flag_balances_rate_implementation() {
+        handlers_v2::balances(context, chain_id, safe_address, fiat, trusted, exclude_spam).await
+    } else {
+        handlers::balances(context, chain_id, safe_address, fiat, trusted, exclude_spam).await
+    }
+}
+
 /// `/v1/balances/supported-fiat-codes` <br/>
 /// Returns [Vec] of [String]
 ///
@@ -78,6 +106,7 @@ pub async fn get_balances(
 #[get("/v1/balances/supported-fiat-codes")]
 pub async fn get_supported_fiat(context: RequestContext) -> ApiResult<content::RawJson<String>> {
     CacheResponse::new(&context, ChainCache::Other)
+        .public()
         .resp_generator(|| fiat_codes(&context))
         .execute()
         .await
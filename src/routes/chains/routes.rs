@@ -25,6 +25,7 @@ pub async fn get_chain(
 ) -> ApiResult<content::RawJson<String>> {
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
         .duration(chain_info_response_cache_duration())
+        .public()
         .resp_generator(|| get_single_chain(&context, &chain_id))
         .execute()
         .await
@@ -48,6 +49,7 @@ pub async fn get_chains(
 ) -> ApiResult<content::RawJson<String>> {
     CacheResponse::new(&context, ChainCache::Other)
         .duration(chain_info_response_cache_duration())
+        .public()
         .resp_generator(|| get_chains_paginated(&context, &cursor))
         .execute()
         .await
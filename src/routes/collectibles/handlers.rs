@@ -1,7 +1,6 @@
 use crate::cache::cache_operations::RequestCached;
 use crate::cache::manager::ChainCache;
 use crate::common::models::page::{Page, PageMetadata};
-use crate::config::collectibles_request_timeout;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::collectibles::models::Collectible as ServiceCollectible;
 use crate::utils::context::RequestContext;
@@ -18,6 +17,7 @@ pub async fn collectibles(
     exclude_spam: Option<bool>,
 ) -> ApiResult<RawJson<String>> {
     let info_provider = DefaultInfoProvider::new(chain_id, &context);
+    let config = info_provider.config();
 
     let url = core_uri!(
         info_provider,
@@ -29,7 +29,9 @@ pub async fn collectibles(
 
     Ok(RawJson(
         RequestCached::new_from_context(url, &context, ChainCache::from(chain_id))
-            .request_timeout(collectibles_request_timeout())
+            .request_timeout(config.collectibles_request_timeout)
+            .stale_while_revalidate(config.stale_while_revalidate_duration)
+            .stale_if_error(config.stale_if_error_duration)
             .execute()
             .await?,
     ))
@@ -45,6 +47,7 @@ pub async fn collectibles_paginated(
     exclude_spam: Option<bool>,
 ) -> ApiResult<Json<Page<ServiceCollectible>>> {
     let info_provider = DefaultInfoProvider::new(chain_id, &context);
+    let config = info_provider.config();
     let page_metadata = cursor
         .as_ref()
         .map(|cursor| PageMetadata::from_cursor(cursor))
@@ -62,7 +65,9 @@ pub async fn collectibles_paginated(
     )?;
 
     let body = RequestCached::new_from_context(url, &context, ChainCache::from(chain_id))
-        .request_timeout(collectibles_request_timeout())
+        .request_timeout(config.collectibles_request_timeout)
+        .stale_while_revalidate(config.stale_while_revalidate_duration)
+        .stale_if_error(config.stale_if_error_duration)
         .execute()
         .await?;
 
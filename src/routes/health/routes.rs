@@ -1,8 +1,11 @@
 use crate::cache::cache_operations::CacheResponse;
 use crate::cache::manager::ChainCache;
+use crate::common::routes::authorization::AuthorizationToken;
+use crate::utils::circuit_breaker;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
 use rocket::response::content;
+use rocket::serde::json::Json;
 use rocket_okapi::openapi;
 
 #[openapi(tag = "Health")]
@@ -13,3 +16,12 @@ pub async fn health(context: RequestContext) -> ApiResult<content::RawJson<Strin
         .execute()
         .await
 }
+
+/// Circuit breakers of the upstream hosts requested by this instance. Not cached, as it reports
+/// the state of the instance serving it, and restricted like the admin routes, as it lists the
+/// upstream hosts.
+#[doc(hidden)]
+#[get("/health/upstreams")]
+pub async fn upstreams(_token: AuthorizationToken) -> Json<Vec<circuit_breaker::CircuitStatus>> {
+    Json(circuit_breaker::statuses())
+}
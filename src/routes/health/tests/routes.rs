@@ -1,7 +1,9 @@
 use crate::tests::main::setup_rocket;
+use crate::utils::circuit_breaker;
 use crate::utils::http_client::MockHttpClient;
 use rocket::http::{ContentType, Header, Status};
 use rocket::local::asynchronous::Client;
+use serde_json::{json, Value};
 
 #[rocket::async_test]
 async fn health() {
@@ -25,3 +27,89 @@ async fn health() {
     assert_eq!(Status::Ok, actual_status);
     assert_eq!("\"\"", actual);
 }
+
+#[rocket::async_test]
+async fn health_not_modified() {
+    let mock_http_client = MockHttpClient::new();
+
+    let client = Client::tracked(
+        setup_rocket(mock_http_client, routes![super::super::routes::health]).await,
+    )
+    .await
+    .expect("valid rocket instance");
+
+    let response = client
+        .get("/health")
+        .header(Header::new("Host", "test.safe.global"))
+        .dispatch()
+        .await;
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+    assert_eq!(
+        Some("private, no-cache"),
+        response.headers().get_one("Cache-Control")
+    );
+
+    let response = client
+        .get("/health")
+        .header(Header::new("Host", "test.safe.global"))
+        .header(Header::new("If-None-Match", etag.to_string()))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::NotModified, response.status());
+    assert_eq!(Some(etag.as_str()), response.headers().get_one("ETag"));
+    assert!(response.into_string().await.unwrap_or_default().is_empty());
+}
+
+#[rocket::async_test]
+async fn health_upstreams_no_token() {
+    let client = Client::tracked(
+        setup_rocket(
+            MockHttpClient::new(),
+            routes![super::super::routes::upstreams],
+        )
+        .await,
+    )
+    .await
+    .expect("valid rocket instance");
+
+    let response = client
+        .get("/health/upstreams")
+        .header(Header::new("Host", "test.safe.global"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn health_upstreams() {
+    circuit_breaker::record("upstream.health.test", false);
+
+    let client = Client::tracked(
+        setup_rocket(
+            MockHttpClient::new(),
+            routes![super::super::routes::upstreams],
+        )
+        .await,
+    )
+    .await
+    .expect("valid rocket instance");
+
+    let response = client
+        .get("/health/upstreams")
+        .header(Header::new("Host", "test.safe.global"))
+        .header(Header::new("Authorization", "Basic test_webhook_token"))
+        .dispatch()
+        .await;
+    let actual_status = response.status();
+    let actual: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+    assert_eq!(Status::Ok, actual_status);
+    assert!(actual.as_array().unwrap().contains(&json!({
+        "host": "upstream.health.test",
+        "state": "closed",
+        "requests": 1,
+        "failures": 1
+    })));
+}
@@ -5,6 +5,7 @@ use crate::cache::manager::ChainCache;
 use crate::common::models::backend::hooks::Payload;
 use crate::common::routes::authorization::AuthorizationToken;
 use crate::config::webhook_token;
+use crate::providers::warm_up::spawn_warm_up;
 use crate::routes::hooks::handlers::invalidate_caches;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
@@ -74,14 +75,13 @@ pub async fn post_flush_events(
     _token: AuthorizationToken,
     invalidation_pattern: Json<InvalidationPattern>,
 ) -> ApiResult<()> {
-    Invalidate::new(
-        invalidation_pattern.0.clone(),
-        context.cache(ChainCache::Mainnet),
-    )
-    .execute()
-    .await;
-    Invalidate::new(invalidation_pattern.0, context.cache(ChainCache::Other))
-        .execute()
-        .await;
+    for cache in context.all_caches() {
+        Invalidate::new(invalidation_pattern.0.clone(), cache)
+            .execute()
+            .await;
+    }
+    if invalidation_pattern.0.matches_warmed_up_data() {
+        spawn_warm_up(context.detach("cache_warm_up"));
+    }
     Ok(())
 }
@@ -3,11 +3,30 @@ use crate::common::models::backend::hooks::{
     ExecutedMultisigTransaction, NewConfirmation, Payload, PayloadDetails,
     PendingMultisigTransaction,
 };
+use crate::config::{with_config, Config, ConfigSource};
 use crate::routes::hooks::handlers::invalidate_caches;
 use mockall::predicate::*;
 use mockall::Sequence;
 use std::sync::Arc;
 
+fn config(scan_fallback: &str) -> Config {
+    Config::from_source(&ConfigSource::from_pairs(&[
+        ("CONFIG_SERVICE_URI", "https://config.service.url"),
+        ("WEBHOOK_TOKEN", "webhook_token"),
+        ("REDIS_URI", "redis://localhost:6379"),
+        ("REDIS_URI_MAINNET", "redis://localhost:6379"),
+        ("CACHE_INVALIDATION_SCAN_FALLBACK", scan_fallback),
+    ]))
+    .unwrap()
+}
+
+// Invalidates through the tag sets only, as once the entries of untagged releases are gone
+async fn invalidate_tagged_only(cache: MockCache, payload: &Payload) {
+    with_config(config("false"), invalidate_caches(Arc::new(cache), payload))
+        .await
+        .unwrap();
+}
+
 #[rocket::async_test]
 async fn invalidate_with_empty_payload() {
     let payload = Payload {
@@ -21,16 +40,18 @@ async fn invalidate_with_empty_payload() {
     mock_cache.expect_fetch().times(0);
     mock_cache.expect_create().times(0);
     mock_cache.expect_invalidate().times(0);
+    mock_cache.expect_invalidate_pattern().times(0);
 
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"));
+        .with(
+            eq("address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"),
+            eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"),
+        );
 
-    invalidate_caches(Arc::new(mock_cache), &payload)
-        .await
-        .unwrap();
+    invalidate_tagged_only(mock_cache, &payload).await;
 }
 
 #[rocket::async_test]
@@ -51,24 +72,27 @@ async fn invalidate_new_confirmation_payload() {
     mock_cache.expect_fetch().times(0);
     mock_cache.expect_create().times(0);
     mock_cache.expect_invalidate().times(0);
+    mock_cache.expect_invalidate_pattern().times(0);
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"))
+        .with(
+            eq("address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"),
+            eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"),
+        )
         .in_sequence(&mut sequence);
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq(
-            "c_re*0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621*",
-        ))
+        .with(
+            eq("hash:0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621"),
+            eq("c_re*0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621*"),
+        )
         .in_sequence(&mut sequence);
 
-    invalidate_caches(Arc::new(mock_cache), &payload)
-        .await
-        .unwrap();
+    invalidate_tagged_only(mock_cache, &payload).await;
 }
 
 #[rocket::async_test]
@@ -92,24 +116,27 @@ async fn invalidate_executed_multisig_transaction_payload() {
     mock_cache.expect_fetch().times(0);
     mock_cache.expect_create().times(0);
     mock_cache.expect_invalidate().times(0);
+    mock_cache.expect_invalidate_pattern().times(0);
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"))
+        .with(
+            eq("address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"),
+            eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"),
+        )
         .in_sequence(&mut sequence);
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq(
-            "c_re*0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621*",
-        ))
+        .with(
+            eq("hash:0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621"),
+            eq("c_re*0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621*"),
+        )
         .in_sequence(&mut sequence);
 
-    invalidate_caches(Arc::new(mock_cache), &payload)
-        .await
-        .unwrap();
+    invalidate_tagged_only(mock_cache, &payload).await;
 }
 
 #[rocket::async_test]
@@ -130,22 +157,56 @@ async fn invalidate_pending_multisig_transaction_payload() {
     mock_cache.expect_fetch().times(0);
     mock_cache.expect_create().times(0);
     mock_cache.expect_invalidate().times(0);
+    mock_cache.expect_invalidate_pattern().times(0);
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"))
+        .with(
+            eq("address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"),
+            eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"),
+        )
         .in_sequence(&mut sequence);
     mock_cache
-        .expect_invalidate_pattern()
+        .expect_invalidate_tagged()
         .times(1)
         .return_const(())
-        .with(eq(
-            "c_re*0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621*",
-        ))
+        .with(
+            eq("hash:0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621"),
+            eq("c_re*0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621*"),
+        )
         .in_sequence(&mut sequence);
 
-    invalidate_caches(Arc::new(mock_cache), &payload)
-        .await
-        .unwrap();
+    invalidate_tagged_only(mock_cache, &payload).await;
+}
+
+#[rocket::async_test]
+async fn invalidate_scans_for_untagged_entries_with_scan_fallback() {
+    let payload = Payload {
+        address: "0x1230B3d59858296A31053C1b8562Ecf89A2f888b".to_string(),
+        chain_id: "1".to_string(),
+        details: None,
+    };
+
+    let mut mock_cache = MockCache::new();
+    mock_cache
+        .expect_invalidate_tagged()
+        .times(1)
+        .return_const(())
+        .with(
+            eq("address:0x1230b3d59858296a31053c1b8562ecf89a2f888b"),
+            eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"),
+        );
+    mock_cache
+        .expect_invalidate_pattern()
+        .times(1)
+        .return_const(())
+        .with(eq("c_re*0x1230B3d59858296A31053C1b8562Ecf89A2f888b*"));
+
+    with_config(
+        config("true"),
+        invalidate_caches(Arc::new(mock_cache), &payload),
+    )
+    .await
+    .unwrap();
 }
@@ -2,6 +2,7 @@ use super::frontend_models::Message as FrontendMessage;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::messages::backend_models::Message;
 use crate::routes::messages::message_mapper::map_message;
+use crate::routes::messages::MessagesEnabled;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
 use crate::utils::http_client::Request;
@@ -9,6 +10,7 @@ use rocket::response::content;
 
 #[get("/v1/chains/<chain_id>/messages/<message_hash>")]
 pub async fn route(
+    _enabled: MessagesEnabled,
     context: RequestContext,
     chain_id: String,
     message_hash: String,
@@ -4,6 +4,7 @@ use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::messages::backend_models::Message;
 use crate::routes::messages::get_messages::MessageItem::DateLabel;
 use crate::routes::messages::message_mapper::map_message;
+use crate::routes::messages::MessagesEnabled;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::{ApiError, ApiResult, ErrorDetails};
 use crate::utils::http_client::Request;
@@ -27,6 +28,7 @@ enum MessageItem {
 
 #[get("/v1/chains/<chain_id>/safes/<safe_address>/messages?<cursor>")]
 pub async fn route(
+    _enabled: MessagesEnabled,
     context: RequestContext,
     chain_id: String,
     safe_address: String,
@@ -1,3 +1,8 @@
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+
+use crate::config::is_messages_feature_enabled;
+
 pub mod backend_models;
 pub mod create_message;
 pub mod get_message;
@@ -6,3 +11,22 @@ pub mod update_message;
 
 mod frontend_models;
 mod message_mapper;
+#[cfg(test)]
+mod tests;
+
+/// Request guard of the messages routes: they are always mounted, but forward (and end up not
+/// found) unless `FEATURE_MESSAGES` is enabled, so that toggling it applies on reload
+pub struct MessagesEnabled();
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MessagesEnabled {
+    type Error = ();
+
+    async fn from_request(_request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if is_messages_feature_enabled() {
+            Outcome::Success(MessagesEnabled())
+        } else {
+            Outcome::Forward(())
+        }
+    }
+}
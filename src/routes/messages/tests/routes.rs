@@ -0,0 +1,33 @@
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+
+use crate::config::{self, with_config};
+use crate::tests::main::setup_rocket;
+use crate::utils::http_client::MockHttpClient;
+
+#[rocket::async_test]
+async fn get_message_feature_disabled() {
+    let mock_http_client = {
+        let mut mock_http_client = MockHttpClient::new();
+        mock_http_client.expect_get().times(0);
+        mock_http_client
+    };
+
+    let client = Client::tracked(
+        setup_rocket(mock_http_client, routes![super::super::get_message::route]).await,
+    )
+    .await
+    .expect("valid rocket instance");
+    let mut config = (*config::current()).clone();
+    config.is_messages_feature_enabled = false;
+    let response = with_config(config, async {
+        let mut response = client.get(
+            "/v1/chains/1/messages/0x65df8a1e5a40703d9c67d5df6f9b552d3830faf0507c3d7350ba3764d3a68621",
+        );
+        response.add_header(Header::new("Host", "test.safe.global"));
+        response.dispatch().await
+    })
+    .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+}
@@ -1,4 +1,5 @@
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
+use crate::routes::messages::MessagesEnabled;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
 use crate::utils::http_client::{Request, Response};
@@ -11,6 +12,7 @@ use serde::{Deserialize, Serialize};
     data = "<signature_payload>"
 )]
 pub async fn route(
+    _enabled: MessagesEnabled,
     context: RequestContext,
     chain_id: String,
     message_hash: String,
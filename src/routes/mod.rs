@@ -3,10 +3,10 @@ use rocket::serde::json::{json, Value};
 use rocket::{Catcher, Route};
 use rocket_okapi::openapi_get_routes;
 
-use crate::config::is_messages_feature_enabled;
-
 /// # About endpoint
 pub mod about;
+#[doc(hidden)]
+pub mod admin;
 /// # Balance endpoints
 pub mod balances;
 /// # Chain endpoints
@@ -47,25 +47,26 @@ pub fn active_routes() -> Vec<Route> {
         transactions::routes::post_preview_transaction,
         transactions::routes::post_transaction,
         // This endpoints shouldn't be exposed on swagger
+        about::routes::get_config,
+        about::routes::get_metrics,
         about::routes::redis,
+        admin::routes::get_cache_entry,
+        admin::routes::post_config_reload,
+        admin::routes::post_cache_keys,
+        admin::routes::post_flush_dry_run,
         hooks::routes::update,
         hooks::routes::post_hook_update,
         hooks::routes::post_hooks_events,
         hooks::routes::post_flush_events,
         hooks::routes::flush,
+        health::routes::upstreams,
+        // Only served while FEATURE_MESSAGES is enabled, see messages::MessagesEnabled
+        messages::create_message::route,
+        messages::get_message::route,
+        messages::get_messages::route,
+        messages::update_message::route,
     ];
 
-    let messages_routes = if is_messages_feature_enabled() {
-        routes![
-            messages::create_message::route,
-            messages::get_message::route,
-            messages::get_messages::route,
-            messages::update_message::route,
-        ]
-    } else {
-        routes![]
-    };
-
     let openapi = openapi_get_routes![
         about::routes::backbone,
         about::routes::get_about,
@@ -94,7 +95,7 @@ pub fn active_routes() -> Vec<Route> {
         transactions::routes::get_multisig_transactions,
         health::routes::health
     ];
-    return [&no_openapi[..], &messages_routes[..], &openapi[..]].concat();
+    return [&no_openapi[..], &openapi[..]].concat();
 }
 
 #[doc(hidden)]
@@ -1,7 +1,6 @@
 use crate::cache::cache_operations::RequestCached;
 use crate::cache::manager::ChainCache;
 use crate::common::models::backend::safe_apps::SafeApp as BackendSafeApp;
-use crate::config::safe_apps_cache_duration;
 use crate::routes::safe_apps::models::SafeApp;
 use crate::utils::context::RequestContext;
 use crate::utils::errors::ApiResult;
@@ -12,6 +11,7 @@ pub async fn safe_apps(
     client_url: &Option<String>,
     url: &Option<String>,
 ) -> ApiResult<Vec<SafeApp>> {
+    let config = context.chain_config(chain_id);
     let url = config_uri!(
         "/v1/safe-apps/?chainId={}&clientUrl={}&url={}",
         chain_id,
@@ -19,7 +19,7 @@ pub async fn safe_apps(
         url.as_deref().unwrap_or("")
     );
     let data = RequestCached::new_from_context(url, &context, ChainCache::from(chain_id.as_str()))
-        .cache_duration(safe_apps_cache_duration())
+        .cache_duration(config.safe_apps_cache_duration)
         .execute()
         .await?;
 
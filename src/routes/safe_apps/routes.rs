@@ -77,6 +77,7 @@ pub async fn get_safe_apps(
     client_url: Option<String>,
     url: Option<String>,
 ) -> ApiResult<content::RawJson<String>> {
+    context.response_hints().public();
     Ok(content::RawJson(serde_json::to_string(
         &safe_apps(&context, &chain_id, &client_url, &url).await?,
     )?))
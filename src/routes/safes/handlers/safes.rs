@@ -3,9 +3,7 @@ use crate::cache::manager::ChainCache;
 use crate::common::models::backend::transactions::{MultisigTransaction, Transaction};
 use crate::common::models::backend::transfers::Transfer;
 use crate::common::models::page::{Page, SafeList};
-use crate::config::{
-    is_messages_feature_enabled, owners_for_safes_cache_duration, transaction_request_timeout,
-};
+use crate::config::is_messages_feature_enabled;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::messages::backend_models::Message;
 use crate::routes::safes::models::{SafeLastChanges, SafeState};
@@ -96,8 +94,9 @@ async fn get_last_collectible(
         safe_address,
     )?;
 
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let transaction: Page<Transfer> = serde_json::from_str(&body)?;
@@ -129,8 +128,9 @@ async fn get_last_queued_tx(
         safe_address,
     )?;
 
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let transaction: Page<MultisigTransaction> = serde_json::from_str(&body)?;
@@ -157,8 +157,9 @@ async fn get_last_history_tx(
         safe_address
     )?;
 
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let transaction: Page<Transaction> = serde_json::from_str(&body)?;
@@ -189,7 +190,7 @@ pub async fn get_owners_for_safe(
 
     let url = core_uri!(info_provider, "/v1/owners/{}/safes/", owner_address)?;
     let body = RequestCached::new_from_context(url, context, ChainCache::from(chain_id))
-        .cache_duration(owners_for_safes_cache_duration())
+        .cache_duration(info_provider.config().owners_for_safes_cache_duration)
         .execute()
         .await?;
 
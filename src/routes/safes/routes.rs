@@ -1,6 +1,5 @@
 use crate::cache::cache_operations::CacheResponse;
 use crate::cache::manager::ChainCache;
-use crate::config::owners_for_safes_cache_duration;
 use crate::routes::safes::handlers::estimations;
 use crate::routes::safes::handlers::safes::{get_owners_for_safe, get_safe_info_ex};
 use crate::routes::safes::models::SafeTransactionEstimationRequest;
@@ -9,6 +8,7 @@ use crate::utils::errors::ApiResult;
 use rocket::response::content;
 use rocket::serde::json::{Error, Json};
 use rocket_okapi::openapi;
+use std::sync::Arc;
 /// `/v1/chains/<chain_id>/safes/<safe_address>` <br />
 /// Returns [SafeState](crate::routes::safes::models::SafeState)
 #[openapi(tag = "Safes")]
@@ -18,7 +18,17 @@ pub async fn get_safe_info(
     chain_id: String,
     safe_address: String,
 ) -> ApiResult<content::RawJson<String>> {
+    let config = context.chain_config(&chain_id);
+    let background = Arc::new(context.detach(&context.request_id));
+    let (background_chain_id, background_safe_address) = (chain_id.clone(), safe_address.clone());
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
+        .stale_while_revalidate(config.stale_while_revalidate_duration, move || {
+            let context = background.clone();
+            let chain_id = background_chain_id.clone();
+            let safe_address = background_safe_address.clone();
+            async move { get_safe_info_ex(&context, &chain_id, &safe_address).await }
+        })
+        .stale_if_error(config.stale_if_error_duration)
         .resp_generator(|| get_safe_info_ex(&context, &chain_id, &safe_address))
         .execute()
         .await
@@ -35,9 +45,10 @@ pub async fn get_owners(
     chain_id: String,
     owner_address: String,
 ) -> ApiResult<content::RawJson<String>> {
+    let config = context.chain_config(&chain_id);
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
         .resp_generator(|| get_owners_for_safe(&context, &chain_id, &owner_address))
-        .duration(owners_for_safes_cache_duration())
+        .duration(config.owners_for_safes_cache_duration)
         .execute()
         .await
 }
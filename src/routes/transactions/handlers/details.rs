@@ -1,8 +1,6 @@
-use crate::cache::cache_operations::RequestCached;
 use crate::common::models::backend::transactions::{ModuleTransaction, MultisigTransaction};
 use crate::common::models::backend::transfers::Transfer;
 use crate::common::models::page::Page;
-use crate::config::transaction_request_timeout;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::transactions::models::details::TransactionDetails;
 use crate::routes::transactions::models::{TransactionIdParts, ID_SEPARATOR};
@@ -18,8 +16,9 @@ pub async fn get_multisig_transaction_details(
     safe_tx_hash: &str,
 ) -> ApiResult<TransactionDetails> {
     let url = core_uri!(info_provider, "/v1/multisig-transactions/{}/", safe_tx_hash)?;
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let multisig_tx: MultisigTransaction = serde_json::from_str(&body)?;
@@ -51,8 +50,9 @@ async fn get_ethereum_transaction_details(
         tx_hash
     )?;
     debug!("url: {}", url);
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let transfers: Page<Transfer> = serde_json::from_str(&body)?;
@@ -86,8 +86,9 @@ async fn get_module_transaction_details(
     )?;
 
     debug!("url: {}", url);
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let transactions: Page<ModuleTransaction> = serde_json::from_str(&body)?;
@@ -1,7 +1,6 @@
 use super::commons::get_backend_page;
 use crate::common::models::backend::transactions::ModuleTransaction;
 use crate::common::models::page::{Page, PageMetadata};
-use crate::config::transaction_request_timeout;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::transactions::filters::module::ModuleFilters;
 use crate::routes::transactions::handlers::offset_page_meta;
@@ -31,7 +30,7 @@ pub async fn get_module_transactions(
         &context,
         chain_id,
         &url,
-        transaction_request_timeout(),
+        info_provider.config().transaction_request_timeout,
         &page_meta,
         filters,
     )
@@ -1,7 +1,6 @@
 use super::commons::get_backend_page;
 use crate::common::models::backend::transactions::MultisigTransaction;
 use crate::common::models::page::{Page, PageMetadata};
-use crate::config::transaction_request_timeout;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::transactions::filters::multisig::MultisigFilters;
 use crate::routes::transactions::handlers::offset_page_meta;
@@ -30,7 +29,7 @@ pub async fn get_multisig_transactions(
         &context,
         chain_id,
         &url,
-        transaction_request_timeout(),
+        info_provider.config().transaction_request_timeout,
         &page_meta,
         filters,
     )
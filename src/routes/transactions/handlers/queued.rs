@@ -2,7 +2,6 @@ use crate::cache::cache_operations::RequestCached;
 use crate::cache::manager::ChainCache;
 use crate::common::models::backend::transactions::MultisigTransaction;
 use crate::common::models::page::{Page, PageMetadata};
-use crate::config::transaction_request_timeout;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::transactions::handlers::offset_page_meta;
 use crate::routes::transactions::models::summary::{ConflictType, Label, TransactionListItem};
@@ -43,7 +42,7 @@ pub async fn get_queued_transactions(
     )?;
 
     let body = RequestCached::new_from_context(url, context, ChainCache::from(chain_id.as_str()))
-        .request_timeout(transaction_request_timeout())
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await?;
     let mut backend_transactions: Page<MultisigTransaction> = serde_json::from_str(&body)?;
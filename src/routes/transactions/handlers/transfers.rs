@@ -1,6 +1,5 @@
 use crate::common::models::backend::transfers::Transfer;
 use crate::common::models::page::{Page, PageMetadata};
-use crate::config::transaction_request_timeout;
 use crate::providers::info::{DefaultInfoProvider, InfoProvider};
 use crate::routes::transactions::filters::transfer::TransferFilters;
 use crate::routes::transactions::handlers::offset_page_meta;
@@ -31,7 +30,7 @@ pub async fn get_incoming_transfers(
         &context,
         chain_id,
         &url,
-        transaction_request_timeout(),
+        info_provider.config().transaction_request_timeout,
         &page_meta,
         filters,
     )
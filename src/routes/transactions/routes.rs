@@ -4,7 +4,6 @@ use rocket_okapi::openapi;
 
 use crate::cache::cache_operations::CacheResponse;
 use crate::cache::manager::ChainCache;
-use crate::config::tx_queued_cache_duration;
 use crate::routes::transactions::filters::module::ModuleFilters;
 use crate::routes::transactions::filters::multisig::MultisigFilters;
 use crate::routes::transactions::filters::transfer::TransferFilters;
@@ -137,6 +136,7 @@ pub async fn get_transactions_history(
     timezone_offset: Option<String>,
 ) -> ApiResult<content::RawJson<String>> {
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
+        .query_default("timezone_offset", "0")
         .resp_generator(|| {
             history::get_history_transactions(
                 &context,
@@ -185,7 +185,10 @@ pub async fn get_transactions_queued(
     timezone_offset: Option<String>,
     trusted: Option<bool>,
 ) -> ApiResult<content::RawJson<String>> {
+    let config = context.chain_config(&chain_id);
     CacheResponse::new(&context, ChainCache::from(chain_id.as_str()))
+        .query_default("timezone_offset", "0")
+        .query_default("trusted", "true")
         .resp_generator(|| {
             queued::get_queued_transactions(
                 &context,
@@ -196,7 +199,7 @@ pub async fn get_transactions_queued(
                 &trusted,
             )
         })
-        .duration(tx_queued_cache_duration())
+        .duration(config.tx_queued_cache_duration)
         .execute()
         .await
 }
@@ -3,8 +3,9 @@ use std::sync::Arc;
 use dotenv::dotenv;
 use rocket::{Build, Rocket, Route};
 
-use crate::cache::manager::ChainCache;
 use crate::cache::{Cache, MockCache};
+use crate::config::{current, Config};
+use crate::utils::cache_headers::CacheHeaders;
 use crate::utils::http_client::{HttpClient, MockHttpClient};
 use crate::{create_cache_manager, RedisCacheManager};
 
@@ -15,19 +16,15 @@ pub async fn setup_rocket(
 ) -> Rocket<Build> {
     dotenv().ok();
     let cache_manager = create_cache_manager().await;
-    cache_manager
-        .cache_for_chain(ChainCache::Mainnet)
-        .invalidate_pattern("*")
-        .await;
-    cache_manager
-        .cache_for_chain(ChainCache::Other)
-        .invalidate_pattern("*")
-        .await;
+    for cache in cache_manager.all_caches() {
+        cache.invalidate_pattern("*").await;
+    }
 
     rocket::build()
         .mount("/", routes)
         .manage(Arc::new(mock_http_client) as Arc<dyn HttpClient>)
         .manage(Arc::new(cache_manager) as Arc<dyn RedisCacheManager>)
+        .attach(CacheHeaders())
 }
 
 #[cfg(test)]
@@ -44,6 +41,17 @@ pub fn setup_rocket_with_mock_cache(
         .manage(Arc::new(mock_cache) as Arc<dyn Cache>)
 }
 
+/// The current configuration with `WEBHOOK_TOKEN` set to `token`, to dispatch requests to
+/// authorized routes [with_config] instead of setting the token in the environment
+///
+/// [with_config]: crate::config::with_config
+#[cfg(test)]
+pub fn config_with_webhook_token(token: &str) -> Config {
+    let mut config = Config::clone(&current());
+    config.webhook_token = String::from(token);
+    config
+}
+
 #[rocket::async_test]
 pub async fn main_produces_valid_rocket_instance() {
     let _ = crate::rocket().await;
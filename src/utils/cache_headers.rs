@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Method, Status};
+use rocket::{Request, Response};
+
+use crate::utils::digest;
+
+/// Caching related details of a response, collected while it is generated (see
+/// [crate::utils::context::RequestContext::response_hints]) and turned into headers by
+/// [CacheHeaders]
+#[derive(Clone, Default)]
+pub struct ResponseHints(Arc<Mutex<Hints>>);
+
+#[derive(Default)]
+struct Hints {
+    etag: Option<String>,
+    max_age: Option<usize>,
+    stale_while_revalidate: Option<usize>,
+    public: bool,
+}
+
+impl ResponseHints {
+    fn hints(&self) -> MutexGuard<'_, Hints> {
+        self.0.lock().expect("Response hints lock poisoned")
+    }
+
+    /// Sets a strong ETag computed from `body`
+    pub fn etag_from_body(&self, body: &str) {
+        self.hints().etag = Some(format!("\"{}\"", digest(body)));
+    }
+
+    pub fn etag(&self) -> Option<String> {
+        self.hints().etag.clone()
+    }
+
+    /// Allows clients to reuse the response for `duration` milliseconds, and to keep using it
+    /// for `stale_while_revalidate` more while refreshing it. A response built from several
+    /// cached parts is only valid as long as the shortest lived of them. Only applies to
+    /// [ResponseHints::public] responses.
+    pub fn cache_for(&self, duration: usize, stale_while_revalidate: usize) {
+        let mut hints = self.hints();
+        hints.max_age = Some(
+            hints
+                .max_age
+                .map_or(duration, |max_age| max_age.min(duration)),
+        );
+        hints.stale_while_revalidate = Some(
+            hints
+                .stale_while_revalidate
+                .map_or(stale_while_revalidate, |stale| {
+                    stale.min(stale_while_revalidate)
+                }),
+        );
+    }
+
+    /// Allows shared caches (CDNs, proxies) to store the response. Only meant for responses
+    /// which do not depend on the user, e.g. chain configurations.
+    pub fn public(&self) {
+        self.hints().public = true;
+    }
+
+    /// Only [ResponseHints::public] responses get a `max-age`. Others may be invalidated at any
+    /// time (e.g. by the transaction service hooks), so clients have to revalidate them on every
+    /// use, which their ETag makes cheap.
+    pub fn cache_control(&self) -> Option<String> {
+        let hints = self.hints();
+        let max_age = match (hints.public, hints.max_age) {
+            (true, Some(max_age)) => max_age,
+            (_, None) if hints.etag.is_none() => return None,
+            _ => return Some(String::from("private, no-cache")),
+        };
+        let mut directives = vec![
+            String::from("public"),
+            format!("max-age={}", max_age / 1000),
+        ];
+        if let Some(stale) = hints.stale_while_revalidate.filter(|stale| *stale >= 1000) {
+            directives.push(format!("stale-while-revalidate={}", stale / 1000));
+        }
+        Some(directives.join(", "))
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.hints().public
+    }
+}
+
+pub struct CacheHeaders();
+
+#[rocket::async_trait]
+impl Fairing for CacheHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Add caching headers to cached responses",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let hints = request.local_cache(ResponseHints::default);
+        if response.status() != Status::Ok {
+            return;
+        }
+        let is_read = request.method() == Method::Get || request.method() == Method::Head;
+        if let Some(cache_control) = hints.cache_control().filter(|_| is_read) {
+            response.set_header(Header::new("Cache-Control", cache_control));
+            if hints.is_public() {
+                // Compression may be applied by proxies in front of the gateway
+                response.set_header(Header::new("Vary", "Accept-Encoding"));
+            }
+        }
+        let etag = match hints.etag() {
+            Some(etag) => etag,
+            None => return,
+        };
+        let not_modified = request
+            .headers()
+            .get("If-None-Match")
+            .any(|if_none_match| etag_matches(if_none_match, &etag));
+
+        response.set_header(Header::new("ETag", etag));
+        if is_read && not_modified {
+            response.set_status(Status::NotModified);
+            response.remove_header("Content-Type");
+            let _ = response.body_mut().take();
+        }
+    }
+}
+
+/// Weak comparison of `etag` against the list of entity tags of an `If-None-Match` header, as
+/// required by RFC 7232
+pub(super) fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+use crate::config::{
+    circuit_breaker_enabled, circuit_breaker_failure_rate, circuit_breaker_min_requests,
+    circuit_breaker_open_duration, circuit_breaker_window,
+};
+use crate::monitoring::metrics::{increment_counter, set_gauge};
+use crate::utils::errors::{ApiError, ApiResult};
+
+lazy_static! {
+    static ref CIRCUITS: Mutex<HashMap<String, Circuit>> = Mutex::new(HashMap::new());
+}
+
+pub struct CircuitSettings {
+    pub failure_rate: f64,
+    pub min_requests: u32,
+    pub window: Duration,
+    pub open_duration: Duration,
+}
+
+impl CircuitSettings {
+    pub fn from_config() -> Self {
+        CircuitSettings {
+            failure_rate: circuit_breaker_failure_rate(),
+            min_requests: circuit_breaker_min_requests(),
+            window: Duration::from_millis(circuit_breaker_window()),
+            open_duration: Duration::from_millis(circuit_breaker_open_duration()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CircuitState {
+    /// Requests are sent
+    Closed,
+    /// Requests fail fast
+    Open,
+    /// A single probe request is sent, closing the circuit again if it succeeds
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+
+    // Value of the `http_client_circuit_state` gauge
+    fn as_gauge(&self) -> f64 {
+        match self {
+            CircuitState::Closed => 0.0,
+            CircuitState::HalfOpen => 1.0,
+            CircuitState::Open => 2.0,
+        }
+    }
+}
+
+/// Circuit of an upstream host, opening once the share of failed requests within a window reaches
+/// [CircuitSettings::failure_rate]
+pub struct Circuit {
+    state: CircuitState,
+    window_start: Instant,
+    requests: u32,
+    failures: u32,
+    // When the circuit opened, or when the last probe was let through while half open
+    since: Instant,
+}
+
+impl Circuit {
+    pub fn new(now: Instant) -> Self {
+        Circuit {
+            state: CircuitState::Closed,
+            window_start: now,
+            requests: 0,
+            failures: 0,
+            since: now,
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    /// Whether a request can be sent. Half opens the circuit once it was open for
+    /// [CircuitSettings::open_duration], letting a probe through.
+    pub fn try_acquire(&mut self, settings: &CircuitSettings, now: Instant) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            // A probe whose outcome is never recorded (e.g. its request was dropped) does not
+            // keep the circuit half open forever
+            CircuitState::Open | CircuitState::HalfOpen
+                if now.saturating_duration_since(self.since) >= settings.open_duration =>
+            {
+                self.state = CircuitState::HalfOpen;
+                self.since = now;
+                true
+            }
+            CircuitState::Open | CircuitState::HalfOpen => false,
+        }
+    }
+
+    pub fn record(&mut self, success: bool, settings: &CircuitSettings, now: Instant) {
+        match self.state {
+            CircuitState::Closed => {
+                if now.saturating_duration_since(self.window_start) >= settings.window {
+                    self.reset(now);
+                }
+                self.requests += 1;
+                if !success {
+                    self.failures += 1;
+                }
+                if self.requests >= settings.min_requests
+                    && f64::from(self.failures) >= settings.failure_rate * f64::from(self.requests)
+                {
+                    self.open(now);
+                }
+            }
+            CircuitState::HalfOpen if success => {
+                self.state = CircuitState::Closed;
+                self.reset(now);
+            }
+            CircuitState::HalfOpen => self.open(now),
+            // Outcome of a request sent before the circuit opened
+            CircuitState::Open => {}
+        }
+    }
+
+    fn open(&mut self, now: Instant) {
+        self.state = CircuitState::Open;
+        self.since = now;
+    }
+
+    fn reset(&mut self, now: Instant) {
+        self.window_start = now;
+        self.requests = 0;
+        self.failures = 0;
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitStatus {
+    pub host: String,
+    pub state: &'static str,
+    pub requests: u32,
+    pub failures: u32,
+}
+
+/// Fails fast with a 503 while the circuit of `host` is open
+pub fn acquire(host: &str) -> ApiResult<()> {
+    if !circuit_breaker_enabled() {
+        return Ok(());
+    }
+    let settings = CircuitSettings::from_config();
+    let acquired = update(host, |circuit| {
+        circuit.try_acquire(&settings, Instant::now())
+    });
+    if acquired {
+        Ok(())
+    } else {
+        increment_counter("http_client_circuit_rejections_total", &[("host", host)]);
+        Err(ApiError::new_upstream_unavailable(format!(
+            "Upstream {} is unavailable, retry later",
+            host
+        )))
+    }
+}
+
+/// Records the outcome of a request to `host`, acquired through [acquire]
+pub fn record(host: &str, success: bool) {
+    if !circuit_breaker_enabled() {
+        return;
+    }
+    let settings = CircuitSettings::from_config();
+    update(host, |circuit| {
+        circuit.record(success, &settings, Instant::now())
+    });
+}
+
+/// Circuits of the upstream hosts requested so far, sorted by host
+pub fn statuses() -> Vec<CircuitStatus> {
+    let circuits = CIRCUITS.lock().expect("Circuits lock poisoned");
+    let mut statuses: Vec<CircuitStatus> = circuits
+        .iter()
+        .map(|(host, circuit)| CircuitStatus {
+            host: host.to_string(),
+            state: circuit.state.as_str(),
+            requests: circuit.requests,
+            failures: circuit.failures,
+        })
+        .collect();
+    statuses.sort_by(|left, right| left.host.cmp(&right.host));
+    statuses
+}
+
+fn update<T>(host: &str, operation: impl FnOnce(&mut Circuit) -> T) -> T {
+    let mut circuits = CIRCUITS.lock().expect("Circuits lock poisoned");
+    let circuit = circuits
+        .entry(host.to_string())
+        .or_insert_with(|| Circuit::new(Instant::now()));
+    let previous = circuit.state;
+    let result = operation(circuit);
+    let state = circuit.state;
+    if state != previous {
+        log::warn!("Circuit of {} is {}", host, state.as_str());
+        increment_counter(
+            "http_client_circuit_transitions_total",
+            &[("host", host), ("state", state.as_str())],
+        );
+    }
+    set_gauge(
+        "http_client_circuit_state",
+        &[("host", host)],
+        state.as_gauge(),
+    );
+    result
+}
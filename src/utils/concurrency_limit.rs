@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use rocket::tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use rocket::tokio::time::timeout;
+use serde::{Serialize, Serializer};
+
+use crate::config::{upstream_max_concurrent_requests, upstream_queue_timeout};
+use crate::monitoring::metrics::{increment_counter, observe_duration, set_gauge};
+use crate::utils::errors::{ApiError, ApiResult};
+
+lazy_static! {
+    static ref UPSTREAM_LIMITS: ConcurrencyLimits = ConcurrencyLimits::default();
+}
+
+/// Limits of the requests in flight to specific upstream hosts, overriding the one of every other
+/// host. Hosts include their port unless it is the default one, e.g. `localhost:8000`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostLimits(BTreeMap<String, usize>);
+
+impl HostLimits {
+    pub fn limit_for(&self, host: &str) -> Option<usize> {
+        self.0.get(host).copied()
+    }
+}
+
+impl FromStr for HostLimits {
+    type Err = String;
+
+    /// Parses limits in the `host=limit;host=limit` format
+    fn from_str(limits: &str) -> Result<Self, Self::Err> {
+        let mut host_limits: BTreeMap<String, usize> = BTreeMap::new();
+        for entry in limits.split(';').map(str::trim) {
+            if entry.is_empty() {
+                continue;
+            }
+            let (host, limit) = entry
+                .split_once('=')
+                .and_then(|(host, limit)| {
+                    let host = host.trim().to_lowercase();
+                    Some((host, limit.trim().parse().ok()?)).filter(|(host, _)| !host.is_empty())
+                })
+                .ok_or_else(|| format!("invalid entry {}", entry))?;
+            host_limits.insert(host, limit);
+        }
+        Ok(HostLimits(host_limits))
+    }
+}
+
+impl fmt::Display for HostLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(host, limit)| format!("{}={}", host, limit))
+            .collect();
+        write!(f, "{}", entries.join(";"))
+    }
+}
+
+impl Serialize for HostLimits {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Caps the requests in flight to every host within this process. Requests over the limit of their
+/// host wait for one of them to finish, failing if they waited for too long.
+#[derive(Default)]
+pub struct ConcurrencyLimits {
+    semaphores: Mutex<HashMap<String, HostSemaphore>>,
+}
+
+struct HostSemaphore {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+/// Held while a request is in flight
+pub struct Permit {
+    host: String,
+    limit: usize,
+    semaphore: Option<Arc<Semaphore>>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Permit {
+    fn unlimited(host: &str) -> Self {
+        Permit {
+            host: host.to_string(),
+            limit: 0,
+            semaphore: None,
+            permit: None,
+        }
+    }
+
+    fn report_in_flight(&self) {
+        if let Some(semaphore) = &self.semaphore {
+            let in_flight = self.limit.saturating_sub(semaphore.available_permits());
+            set_gauge(
+                "http_client_in_flight_requests",
+                &[("host", &self.host)],
+                in_flight as f64,
+            );
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        self.report_in_flight();
+    }
+}
+
+impl ConcurrencyLimits {
+    /// Waits at most `max_wait` for one of the `limit` permits of `host`. A `limit` of 0 does not
+    /// cap the requests to it.
+    pub async fn acquire(&self, host: &str, limit: usize, max_wait: Duration) -> ApiResult<Permit> {
+        if limit == 0 {
+            return Ok(Permit::unlimited(host));
+        }
+        let semaphore = self.semaphore(host, limit);
+        let started = Instant::now();
+        let acquired = timeout(max_wait, semaphore.clone().acquire_owned()).await;
+        observe_duration(
+            "http_client_queue_duration_seconds",
+            &[("host", host)],
+            started.elapsed(),
+        );
+        match acquired {
+            Ok(Ok(permit)) => {
+                let permit = Permit {
+                    host: host.to_string(),
+                    limit,
+                    semaphore: Some(semaphore),
+                    permit: Some(permit),
+                };
+                permit.report_in_flight();
+                Ok(permit)
+            }
+            // Semaphores are never closed, so waiting can only time out
+            _ => {
+                increment_counter("http_client_queue_timeouts_total", &[("host", host)]);
+                Err(ApiError::new_upstream_unavailable(format!(
+                    "Too many requests to {} in flight, retry later",
+                    host
+                )))
+            }
+        }
+    }
+
+    // The semaphore of a host is replaced when its limit changes, e.g. on a configuration reload.
+    // Requests in flight release their permits into the replaced one, so until they finish the
+    // host may get up to both limits of requests.
+    fn semaphore(&self, host: &str, limit: usize) -> Arc<Semaphore> {
+        let mut semaphores = self.semaphores.lock().expect("Semaphores lock poisoned");
+        match semaphores.get(host) {
+            Some(current) if current.limit == limit => current.semaphore.clone(),
+            _ => {
+                let semaphore = Arc::new(Semaphore::new(limit));
+                semaphores.insert(
+                    host.to_string(),
+                    HostSemaphore {
+                        limit,
+                        semaphore: semaphore.clone(),
+                    },
+                );
+                semaphore
+            }
+        }
+    }
+}
+
+/// Permit to send a request to an upstream `host`, waiting for the configured queue timeout at
+/// most, or for `budget` if shorter
+pub async fn acquire(host: &str, budget: Duration) -> ApiResult<Permit> {
+    let max_wait = Duration::from_millis(upstream_queue_timeout()).min(budget);
+    UPSTREAM_LIMITS
+        .acquire(host, upstream_max_concurrent_requests(host), max_wait)
+        .await
+}
@@ -6,7 +6,8 @@ use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
 
 use crate::cache::manager::ChainCache;
 use crate::cache::Cache;
-use crate::config::scheme;
+use crate::config::{self, Config};
+use crate::utils::cache_headers::ResponseHints;
 use crate::utils::http_client::HttpClient;
 use crate::RedisCacheManager;
 
@@ -15,6 +16,8 @@ pub struct RequestContext {
     pub host: String,
     http_client: Arc<dyn HttpClient>,
     cache_manager: Arc<dyn RedisCacheManager>,
+    response_hints: ResponseHints,
+    config: Arc<Config>,
 }
 
 impl RequestContext {
@@ -26,6 +29,50 @@ impl RequestContext {
         self.cache_manager.cache_for_chain(chain_cache)
     }
 
+    pub fn all_caches(&self) -> Vec<Arc<dyn Cache>> {
+        self.cache_manager.all_caches()
+    }
+
+    /// Configuration the request is handled with
+    pub fn config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    /// [RequestContext::config] with the overrides of `chain_id` applied, see [config::for_chain]
+    pub fn chain_config(&self, chain_id: &str) -> Arc<Config> {
+        config::chain_config(&self.config, chain_id)
+    }
+
+    /// Context for work done outside of a request, e.g. warming up caches
+    pub fn background(
+        request_id: &str,
+        http_client: Arc<dyn HttpClient>,
+        cache_manager: Arc<dyn RedisCacheManager>,
+    ) -> Self {
+        RequestContext {
+            request_id: request_id.to_string(),
+            host: String::new(),
+            http_client,
+            cache_manager,
+            response_hints: ResponseHints::default(),
+            config: config::current(),
+        }
+    }
+
+    /// [RequestContext::background] sharing the client, caches and host of this request, which
+    /// can outlive it
+    pub fn detach(&self, request_id: &str) -> Self {
+        RequestContext {
+            host: self.host.clone(),
+            ..Self::background(request_id, self.http_client(), self.cache_manager.clone())
+        }
+    }
+
+    /// Caching details of the response to this request, sent back to the client as headers
+    pub fn response_hints(&self) -> ResponseHints {
+        self.response_hints.clone()
+    }
+
     #[cfg(test)]
     pub async fn setup_for_test(
         request_id: String,
@@ -33,20 +80,17 @@ impl RequestContext {
         http_client: &Arc<dyn HttpClient>,
         cache_manager: &Arc<dyn RedisCacheManager>,
     ) -> Self {
-        cache_manager
-            .cache_for_chain(ChainCache::Mainnet)
-            .invalidate_pattern("*")
-            .await;
-        cache_manager
-            .cache_for_chain(ChainCache::Other)
-            .invalidate_pattern("*")
-            .await;
+        for cache in cache_manager.all_caches() {
+            cache.invalidate_pattern("*").await;
+        }
 
         RequestContext {
             request_id,
             host,
             http_client: http_client.clone(),
             cache_manager: cache_manager.clone(),
+            response_hints: ResponseHints::default(),
+            config: config::current(),
         }
     }
 }
@@ -66,19 +110,24 @@ impl<'r> FromRequest<'r> for RequestContext {
             .state::<Arc<dyn HttpClient>>()
             .expect("HttpClient unavailable. Is it added to rocket instance?")
             .clone();
+        // Read from the installed configuration, as managed state cannot be swapped on reload
+        let config = config::current();
         let host = request
             .headers()
             .get_one("Host")
             .expect("Request Host must be available");
 
         let uri = request.uri().to_string();
-        let host = format!("{}://{}", scheme(), host.to_string());
+        let host = format!("{}://{}", config.scheme, host.to_string());
+        let response_hints = request.local_cache(ResponseHints::default).clone();
 
         return request::Outcome::Success(RequestContext {
             request_id: uri,
             host,
             cache_manager,
             http_client,
+            response_hints,
+            config,
         });
     }
 }
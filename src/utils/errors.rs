@@ -20,14 +20,17 @@ use thiserror::Error;
 
 pub type ApiResult<T, E = ApiError> = Result<T, E>;
 
-#[derive(Error, Debug, PartialEq, JsonSchema)]
+// Code of the 503s answered by the HTTP client without reaching the upstream
+const UPSTREAM_UNAVAILABLE_CODE: u64 = 1503;
+
+#[derive(Error, Debug, Clone, PartialEq, JsonSchema)]
 #[cfg_attr(test, derive(Serialize, Deserialize))]
 pub struct ApiError {
     pub status: u16,
     pub details: ErrorDetails,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct ErrorDetails {
     pub code: u64,
     pub message: Option<String>,
@@ -91,6 +94,25 @@ impl ApiError {
         )
     }
 
+    /// 503 answered without reaching the upstream (e.g. its circuit is open), which unlike one
+    /// answered by the upstream is never cached
+    pub fn new_upstream_unavailable(message: String) -> Self {
+        Self::new(
+            503,
+            ErrorDetails {
+                code: UPSTREAM_UNAVAILABLE_CODE,
+                message: Some(message),
+                arguments: None,
+                debug: None,
+            },
+        )
+    }
+
+    /// Whether the error was created with [ApiError::new_upstream_unavailable]
+    pub fn is_upstream_unavailable(&self) -> bool {
+        self.status == 503 && self.details.code == UPSTREAM_UNAVAILABLE_CODE
+    }
+
     pub fn new(status_code: u16, message: ErrorDetails) -> Self {
         Self {
             status: status_code,
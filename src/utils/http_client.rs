@@ -1,11 +1,17 @@
 use crate::config::default_request_timeout;
 #[cfg(not(test))]
 use crate::config::internal_client_connect_timeout;
+use crate::monitoring::metrics::increment_counter;
+use crate::utils::circuit_breaker;
+use crate::utils::concurrency_limit;
 use crate::utils::errors::{ApiError, ApiResult};
+use crate::utils::retry::{is_retryable_status, parse_retry_after, RetryPolicy};
 use core::time::Duration;
 use mockall::automock;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, RETRY_AFTER};
+use rocket::tokio::time::sleep;
 use std::collections::HashMap;
+use std::time::{Instant, SystemTime};
 
 #[derive(PartialEq, Debug)]
 pub struct Request {
@@ -93,40 +99,122 @@ pub trait HttpClient: Send + Sync {
 
 #[rocket::async_trait]
 impl HttpClient for reqwest::Client {
+    /// Retries connection errors, timeouts and transient error responses (see
+    /// [is_retryable_status]) as long as the [Request::timeout] of the whole exchange allows it.
+    /// POSTs and DELETEs are not idempotent, so they are never retried.
+    /// Time spent waiting for a permit of the upstream host (see [concurrency_limit::acquire])
+    /// counts towards the same time out.
+    ///
+    /// The permit is taken before the circuit breaker is asked: a half open circuit lets a single
+    /// probe through, whose outcome must be recorded, so the probe must not then fail waiting.
     async fn get(&self, request: Request) -> ApiResult<Response> {
-        let response = self
-            .get(&request.url)
-            .timeout(request.timeout)
-            .headers(map_headers(&request.headers))
-            .send()
+        let host = upstream_host(&request.url);
+        let policy = RetryPolicy::from_config();
+        let deadline = Instant::now() + request.timeout;
+        let mut retries = 0;
+        loop {
+            let permit = concurrency_limit::acquire(
+                &host,
+                deadline.saturating_duration_since(Instant::now()),
+            )
             .await?;
-        Response::from(response).await
+            circuit_breaker::acquire(&host)?;
+            let result = self
+                .get(&request.url)
+                .timeout(deadline.saturating_duration_since(Instant::now()))
+                .headers(map_headers(&request.headers))
+                .send()
+                .await;
+            circuit_breaker::record(&host, is_healthy(&result));
+            let retry = match &result {
+                Ok(response) if is_retryable_status(response.status().as_u16()) => {
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| parse_retry_after(value, SystemTime::now()));
+                    policy
+                        .delay(retries, retry_after)
+                        .map(|delay| (delay, response.status().as_str().to_string()))
+                }
+                // Connect timeouts are bounded by the connect timeout of the client, leaving
+                // time for another attempt
+                Err(error) if error.is_timeout() => policy
+                    .delay(retries, None)
+                    .map(|delay| (delay, String::from("timeout"))),
+                Err(error) if error.is_connect() => policy
+                    .delay(retries, None)
+                    .map(|delay| (delay, String::from("connection"))),
+                _ => None,
+            };
+            match retry {
+                Some((delay, reason))
+                    if delay < deadline.saturating_duration_since(Instant::now()) =>
+                {
+                    increment_counter("http_client_retries_total", &[("reason", &reason)]);
+                    drop(permit);
+                    sleep(delay).await;
+                    retries += 1;
+                }
+                _ => return Response::from(result?).await,
+            }
+        }
     }
 
     async fn post(&self, request: Request) -> ApiResult<Response> {
+        let host = upstream_host(&request.url);
+        let _permit = concurrency_limit::acquire(&host, request.timeout).await?;
+        circuit_breaker::acquire(&host)?;
         let body = request.body.unwrap_or(String::from(""));
-        let response = self
+        let result = self
             .post(&request.url)
             .header(CONTENT_TYPE, "application/json")
             .body(body)
             .headers(map_headers(&request.headers))
             .timeout(request.timeout)
             .send()
-            .await?;
-        Response::from(response).await
+            .await;
+        circuit_breaker::record(&host, is_healthy(&result));
+        Response::from(result?).await
     }
 
     async fn delete(&self, request: Request) -> ApiResult<Response> {
+        let host = upstream_host(&request.url);
+        let _permit = concurrency_limit::acquire(&host, request.timeout).await?;
+        circuit_breaker::acquire(&host)?;
         let body = request.body.unwrap_or(String::from(""));
-        let response = self
+        let result = self
             .delete(&request.url)
             .header(CONTENT_TYPE, "application/json")
             .body(body)
             .headers(map_headers(&request.headers))
             .timeout(request.timeout)
             .send()
-            .await?;
-        Response::from(response).await
+            .await;
+        circuit_breaker::record(&host, is_healthy(&result));
+        Response::from(result?).await
+    }
+}
+
+/// Host (and port, if not the default one) the circuit breaker and concurrency limit of a request
+/// are keyed by
+fn upstream_host(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Client errors are answered by a healthy upstream, so only transport errors and server errors
+/// count as failures
+fn is_healthy(result: &reqwest::Result<reqwest::Response>) -> bool {
+    match result {
+        Ok(response) => !response.status().is_server_error(),
+        Err(error) => error.is_builder() || error.is_redirect(),
     }
 }
 
@@ -1,13 +1,20 @@
 use crate::common::models::data_decoded::ValueDecodedType::InternalTransaction;
 use crate::common::models::data_decoded::{DataDecoded, ParamValue, ValueDecodedType};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE;
+use base64::Engine;
+use openssl::sha::sha256;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
+pub mod cache_headers;
+pub mod circuit_breaker;
+pub mod concurrency_limit;
 pub mod context;
 pub mod cors;
 pub mod errors;
 pub mod http_client;
 pub mod json;
+pub mod retry;
 pub mod transactions;
 pub mod urls;
 
@@ -129,3 +136,10 @@ pub fn hex_hash<T: Hash>(t: &T) -> String {
     t.hash(&mut s);
     format!("{:#x}", s.finish())
 }
+
+/// Short digest of `value` (the first 96 bits of its SHA-256, base64 encoded). Unlike
+/// [hex_hash], it is stable across builds, so it can be shared between gateway instances, e.g. in
+/// cache keys and ETags.
+pub fn digest(value: &str) -> String {
+    BASE64_URL_SAFE.encode(&sha256(value.as_bytes())[..12])
+}
@@ -0,0 +1,57 @@
+use std::time::{Duration, SystemTime};
+
+use chrono::DateTime;
+
+use crate::config::{http_retry_base_delay, http_retry_max_delay, http_retry_max_retries};
+
+/// Retries of idempotent upstream requests failing transiently, delayed by a jittered exponential
+/// backoff unless the upstream asks for a specific delay with `Retry-After`
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config() -> Self {
+        RetryPolicy {
+            max_retries: http_retry_max_retries(),
+            base_delay: Duration::from_millis(http_retry_base_delay()),
+            max_delay: Duration::from_millis(http_retry_max_delay()),
+        }
+    }
+
+    /// Delay before the next attempt once `retries` retries were made, or [None] when they are
+    /// exhausted
+    pub fn delay(&self, retries: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if retries >= self.max_retries {
+            return None;
+        }
+        Some(retry_after.unwrap_or_else(|| self.backoff(retries)))
+    }
+
+    // "Full jitter": uniformly distributed below the capped exponential delay, so that clients
+    // failing at the same time do not retry at the same time
+    fn backoff(&self, retries: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retries))
+            .min(self.max_delay);
+        ceiling.mul_f64(rand::random::<f64>())
+    }
+}
+
+/// Responses worth retrying: rate limited (429) or a gateway failing to reach the upstream
+pub fn is_retryable_status(status_code: u16) -> bool {
+    matches!(status_code, 429 | 502 | 503 | 504)
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = SystemTime::from(DateTime::parse_from_rfc2822(value).ok()?);
+    Some(date.duration_since(now).unwrap_or_default())
+}
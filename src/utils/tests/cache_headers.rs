@@ -0,0 +1,70 @@
+use crate::utils::cache_headers::{etag_matches, ResponseHints};
+
+#[test]
+fn etag_from_body_is_quoted_and_stable() {
+    let hints = ResponseHints::default();
+    assert_eq!(hints.etag(), None);
+
+    hints.etag_from_body("{\"count\":1}");
+    let etag = hints.etag().unwrap();
+    hints.etag_from_body("{\"count\":1}");
+
+    assert_eq!(etag, "\"aupt_mVhmEzcXFTq\"");
+    assert_eq!(hints.etag(), Some(etag.clone()));
+
+    hints.etag_from_body("{\"count\":2}");
+    assert_ne!(hints.etag(), Some(etag));
+}
+
+#[test]
+fn if_none_match_comparison() {
+    assert!(etag_matches("\"0xabc\"", "\"0xabc\""));
+    assert!(etag_matches("\"0x1\", W/\"0xabc\"", "\"0xabc\""));
+    assert!(etag_matches("*", "\"0xabc\""));
+    assert!(!etag_matches("\"0x1\", \"0x2\"", "\"0xabc\""));
+    assert!(!etag_matches("0xabc", "\"0xabc\""));
+}
+
+#[test]
+fn cache_control_uses_shortest_duration() {
+    let hints = ResponseHints::default();
+    assert_eq!(hints.cache_control(), None);
+
+    hints.cache_for(60 * 1000, 0);
+    hints.cache_for(15 * 1000, 30 * 1000);
+    hints.cache_for(30 * 1000, 45 * 1000);
+    hints.public();
+    assert_eq!(
+        hints.cache_control(),
+        Some(String::from("public, max-age=15"))
+    );
+}
+
+#[test]
+fn cache_control_with_stale_while_revalidate() {
+    let hints = ResponseHints::default();
+    hints.cache_for(1500, 10 * 1000);
+    hints.public();
+
+    assert_eq!(
+        hints.cache_control(),
+        Some(String::from("public, max-age=1, stale-while-revalidate=10"))
+    );
+}
+
+#[test]
+fn cache_control_requires_revalidation_of_private_responses() {
+    let hints = ResponseHints::default();
+    hints.cache_for(60 * 1000, 0);
+    assert_eq!(
+        hints.cache_control(),
+        Some(String::from("private, no-cache"))
+    );
+
+    let hints = ResponseHints::default();
+    hints.etag_from_body("{\"count\":1}");
+    assert_eq!(
+        hints.cache_control(),
+        Some(String::from("private, no-cache"))
+    );
+}
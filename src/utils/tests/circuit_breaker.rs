@@ -0,0 +1,129 @@
+use std::time::{Duration, Instant};
+
+use crate::utils::circuit_breaker::{Circuit, CircuitSettings, CircuitState};
+
+fn settings() -> CircuitSettings {
+    CircuitSettings {
+        failure_rate: 0.5,
+        min_requests: 4,
+        window: Duration::from_secs(10),
+        open_duration: Duration::from_secs(5),
+    }
+}
+
+fn open_circuit(now: Instant) -> Circuit {
+    let mut circuit = Circuit::new(now);
+    for _ in 0..4 {
+        circuit.record(false, &settings(), now);
+    }
+    circuit
+}
+
+#[test]
+fn circuit_stays_closed_below_min_requests() {
+    let now = Instant::now();
+    let mut circuit = Circuit::new(now);
+
+    for _ in 0..3 {
+        circuit.record(false, &settings(), now);
+    }
+
+    assert_eq!(CircuitState::Closed, circuit.state());
+    assert!(circuit.try_acquire(&settings(), now));
+}
+
+#[test]
+fn circuit_stays_closed_below_failure_rate() {
+    let now = Instant::now();
+    let mut circuit = Circuit::new(now);
+
+    circuit.record(false, &settings(), now);
+    for _ in 0..3 {
+        circuit.record(true, &settings(), now);
+    }
+
+    assert_eq!(CircuitState::Closed, circuit.state());
+}
+
+#[test]
+fn circuit_opens_at_failure_rate() {
+    let now = Instant::now();
+    let mut circuit = Circuit::new(now);
+
+    circuit.record(true, &settings(), now);
+    circuit.record(false, &settings(), now);
+    circuit.record(true, &settings(), now);
+    circuit.record(false, &settings(), now);
+
+    assert_eq!(CircuitState::Open, circuit.state());
+    assert!(!circuit.try_acquire(&settings(), now + Duration::from_secs(1)));
+}
+
+#[test]
+fn circuit_forgets_failures_of_previous_window() {
+    let now = Instant::now();
+    let mut circuit = Circuit::new(now);
+
+    for _ in 0..3 {
+        circuit.record(false, &settings(), now);
+    }
+    circuit.record(false, &settings(), now + Duration::from_secs(10));
+
+    assert_eq!(CircuitState::Closed, circuit.state());
+}
+
+#[test]
+fn circuit_half_opens_after_open_duration() {
+    let now = Instant::now();
+    let mut circuit = open_circuit(now);
+    let probed_at = now + Duration::from_secs(5);
+
+    assert!(circuit.try_acquire(&settings(), probed_at));
+    assert_eq!(CircuitState::HalfOpen, circuit.state());
+    // Only the probe is let through
+    assert!(!circuit.try_acquire(&settings(), probed_at));
+}
+
+#[test]
+fn circuit_closes_on_successful_probe() {
+    let now = Instant::now();
+    let mut circuit = open_circuit(now);
+    let probed_at = now + Duration::from_secs(5);
+
+    circuit.try_acquire(&settings(), probed_at);
+    circuit.record(true, &settings(), probed_at);
+
+    assert_eq!(CircuitState::Closed, circuit.state());
+    assert!(circuit.try_acquire(&settings(), probed_at));
+    // Failures before the circuit opened are not counted again
+    for _ in 0..3 {
+        circuit.record(false, &settings(), probed_at);
+    }
+    assert_eq!(CircuitState::Closed, circuit.state());
+}
+
+#[test]
+fn circuit_reopens_on_failed_probe() {
+    let now = Instant::now();
+    let mut circuit = open_circuit(now);
+    let probed_at = now + Duration::from_secs(5);
+
+    circuit.try_acquire(&settings(), probed_at);
+    circuit.record(false, &settings(), probed_at);
+
+    assert_eq!(CircuitState::Open, circuit.state());
+    assert!(!circuit.try_acquire(&settings(), probed_at + Duration::from_secs(4)));
+    assert!(circuit.try_acquire(&settings(), probed_at + Duration::from_secs(5)));
+}
+
+#[test]
+fn circuit_lets_another_probe_through_when_one_is_lost() {
+    let now = Instant::now();
+    let mut circuit = open_circuit(now);
+    let probed_at = now + Duration::from_secs(5);
+
+    circuit.try_acquire(&settings(), probed_at);
+
+    assert!(circuit.try_acquire(&settings(), probed_at + Duration::from_secs(5)));
+    assert_eq!(CircuitState::HalfOpen, circuit.state());
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use crate::utils::concurrency_limit::{ConcurrencyLimits, HostLimits};
+
+#[rocket::async_test]
+async fn acquire_within_limit() {
+    let limits = ConcurrencyLimits::default();
+
+    let first = limits
+        .acquire("limit.test", 2, Duration::from_millis(10))
+        .await;
+    let second = limits
+        .acquire("limit.test", 2, Duration::from_millis(10))
+        .await;
+
+    assert!(first.is_ok());
+    assert!(second.is_ok());
+}
+
+#[rocket::async_test]
+async fn acquire_over_limit_times_out() {
+    let limits = ConcurrencyLimits::default();
+    let _permit = limits
+        .acquire("limit.test", 1, Duration::from_millis(10))
+        .await
+        .unwrap();
+
+    let actual = limits
+        .acquire("limit.test", 1, Duration::from_millis(10))
+        .await;
+
+    let error = actual.err().unwrap();
+    assert_eq!(error.status, 503);
+    assert!(error.details.message.unwrap().contains("limit.test"));
+}
+
+#[rocket::async_test]
+async fn acquire_after_permit_released() {
+    let limits = ConcurrencyLimits::default();
+    let permit = limits
+        .acquire("limit.test", 1, Duration::from_millis(10))
+        .await
+        .unwrap();
+    drop(permit);
+
+    let actual = limits
+        .acquire("limit.test", 1, Duration::from_millis(10))
+        .await;
+
+    assert!(actual.is_ok());
+}
+
+#[rocket::async_test]
+async fn acquire_limits_hosts_separately() {
+    let limits = ConcurrencyLimits::default();
+    let _permit = limits
+        .acquire("limit.test", 1, Duration::from_millis(10))
+        .await
+        .unwrap();
+
+    let actual = limits
+        .acquire("other.limit.test", 1, Duration::from_millis(10))
+        .await;
+
+    assert!(actual.is_ok());
+}
+
+#[rocket::async_test]
+async fn acquire_without_limit() {
+    let limits = ConcurrencyLimits::default();
+    let mut permits = vec![];
+
+    for _ in 0..10 {
+        permits.push(
+            limits
+                .acquire("limit.test", 0, Duration::from_millis(10))
+                .await
+                .unwrap(),
+        );
+    }
+
+    assert_eq!(permits.len(), 10);
+}
+
+#[test]
+fn parse_host_limits() {
+    let limits: HostLimits = "Mainnet.example=200; localhost:8000=10;".parse().unwrap();
+
+    assert_eq!(limits.limit_for("mainnet.example"), Some(200));
+    assert_eq!(limits.limit_for("localhost:8000"), Some(10));
+    assert_eq!(limits.limit_for("localhost"), None);
+    assert_eq!(limits.to_string(), "localhost:8000=10;mainnet.example=200");
+}
+
+#[test]
+fn parse_host_limits_invalid_entry() {
+    let actual = "mainnet.example=many;localhost=1".parse::<HostLimits>();
+
+    assert_eq!(
+        actual,
+        Err(String::from("invalid entry mainnet.example=many"))
+    );
+}
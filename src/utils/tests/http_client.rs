@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use rocket::tokio::net::TcpListener;
+use rocket::tokio::time::sleep;
+
+use crate::config::{with_config, Config, ConfigSource};
+use crate::monitoring::metrics::render;
+use crate::utils::concurrency_limit;
+use crate::utils::errors::ApiResult;
+use crate::utils::http_client::{HttpClient, Request, Response};
+
+const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+const BAD_GATEWAY: &str =
+    "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const UNAVAILABLE: &str =
+    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const UNAVAILABLE_FOR_A_MINUTE: &str = concat!(
+    "HTTP/1.1 503 Service Unavailable\r\n",
+    "Retry-After: 60\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+);
+
+fn config(pairs: &[(&str, &str)]) -> Config {
+    let defaults: &[(&str, &str)] = &[
+        ("CONFIG_SERVICE_URI", "https://config.service.url"),
+        ("WEBHOOK_TOKEN", "webhook_token"),
+        ("REDIS_URI", "redis://localhost:6379"),
+        ("REDIS_URI_MAINNET", "redis://localhost:6379"),
+        ("CIRCUIT_BREAKER_ENABLED", "false"),
+        ("HTTP_RETRY_BASE_DELAY", "1"),
+        ("HTTP_RETRY_MAX_DELAY", "1"),
+    ];
+    Config::from_source(&ConfigSource::from_pairs(&[defaults, pairs].concat())).unwrap()
+}
+
+// Answers each request with the next of `responses`, repeating the last one once they run out.
+// Returns the URL listened on and the count of requests received.
+async fn stub_upstream(responses: &[&'static str]) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let received = requests.clone();
+    let responses = responses.to_vec();
+    rocket::tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).await;
+            let index = received.fetch_add(1, Ordering::SeqCst);
+            let response = responses[index.min(responses.len() - 1)];
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+    (url, requests)
+}
+
+async fn send(client: &reqwest::Client, method: &str, url: &str) -> ApiResult<Response> {
+    let request = Request::new(String::from(url));
+    match method {
+        "GET" => HttpClient::get(client, request).await,
+        "POST" => HttpClient::post(client, request).await,
+        _ => HttpClient::delete(client, request).await,
+    }
+}
+
+fn retries_total(reason: &str) -> u64 {
+    let series = format!("http_client_retries_total{{reason=\"{}\"}} ", reason);
+    render()
+        .lines()
+        .find_map(|line| line.strip_prefix(&series))
+        .map_or(0, |value| value.parse().unwrap())
+}
+
+#[rocket::async_test]
+async fn get_retries_transient_errors_and_counts_them() {
+    // The only test answering with a 502, so that no other one adds to its retries
+    let (url, requests) = stub_upstream(&[BAD_GATEWAY, BAD_GATEWAY, OK]).await;
+    let retries_before = retries_total("502");
+
+    let actual = with_config(config(&[("HTTP_RETRY_MAX_RETRIES", "2")]), async {
+        HttpClient::get(&reqwest::Client::new(), Request::new(url)).await
+    })
+    .await;
+
+    assert_eq!(actual.unwrap().body, "ok");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+    assert_eq!(retries_total("502") - retries_before, 2);
+}
+
+#[rocket::async_test]
+async fn get_gives_up_once_retries_are_exhausted() {
+    let (url, requests) = stub_upstream(&[UNAVAILABLE]).await;
+
+    let actual = with_config(config(&[("HTTP_RETRY_MAX_RETRIES", "2")]), async {
+        HttpClient::get(&reqwest::Client::new(), Request::new(url)).await
+    })
+    .await;
+
+    assert_eq!(actual.unwrap_err().status, 503);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[rocket::async_test]
+async fn get_stops_retrying_once_the_timeout_is_spent() {
+    let (url, requests) = stub_upstream(&[UNAVAILABLE]).await;
+    let config = config(&[
+        ("HTTP_RETRY_MAX_RETRIES", "1000"),
+        ("HTTP_RETRY_BASE_DELAY", "20"),
+        ("HTTP_RETRY_MAX_DELAY", "20"),
+    ]);
+    let started = Instant::now();
+
+    let actual = with_config(config, async {
+        let mut request = Request::new(url);
+        request.timeout(Duration::from_millis(200));
+        HttpClient::get(&reqwest::Client::new(), request).await
+    })
+    .await;
+
+    assert_eq!(actual.unwrap_err().status, 503);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let requests = requests.load(Ordering::SeqCst);
+    assert!(1 < requests && requests < 1000);
+}
+
+#[rocket::async_test]
+async fn get_does_not_wait_for_a_retry_after_past_the_timeout() {
+    let (url, requests) = stub_upstream(&[UNAVAILABLE_FOR_A_MINUTE, OK]).await;
+    let started = Instant::now();
+
+    let actual = with_config(config(&[("HTTP_RETRY_MAX_RETRIES", "2")]), async {
+        let mut request = Request::new(url);
+        request.timeout(Duration::from_secs(5));
+        HttpClient::get(&reqwest::Client::new(), request).await
+    })
+    .await;
+
+    assert_eq!(actual.unwrap_err().status, 503);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[rocket::async_test]
+async fn post_and_delete_are_not_retried() {
+    let (post_url, posts) = stub_upstream(&[UNAVAILABLE, OK]).await;
+    let (delete_url, deletes) = stub_upstream(&[UNAVAILABLE, OK]).await;
+    let client = reqwest::Client::new();
+
+    let (post, delete) = with_config(config(&[("HTTP_RETRY_MAX_RETRIES", "2")]), async {
+        let post = HttpClient::post(&client, Request::new(post_url)).await;
+        let delete = HttpClient::delete(&client, Request::new(delete_url)).await;
+        (post, delete)
+    })
+    .await;
+
+    assert_eq!(post.unwrap_err().status, 503);
+    assert_eq!(delete.unwrap_err().status, 503);
+    assert_eq!(posts.load(Ordering::SeqCst), 1);
+    assert_eq!(deletes.load(Ordering::SeqCst), 1);
+}
+
+#[rocket::async_test]
+async fn failures_open_the_circuit_of_every_method() {
+    let config = config(&[
+        ("CIRCUIT_BREAKER_ENABLED", "true"),
+        ("CIRCUIT_BREAKER_MIN_REQUESTS", "2"),
+        ("HTTP_RETRY_MAX_RETRIES", "0"),
+    ]);
+    let client = reqwest::Client::new();
+
+    with_config(config, async {
+        for method in &["GET", "POST", "DELETE"] {
+            let (url, requests) = stub_upstream(&[UNAVAILABLE]).await;
+
+            for _ in 0..2 {
+                let actual = send(&client, method, &url).await.unwrap_err();
+                assert!(!actual.is_upstream_unavailable(), "{}", method);
+            }
+            let actual = send(&client, method, &url).await.unwrap_err();
+
+            assert!(actual.is_upstream_unavailable(), "{}", method);
+            assert_eq!(requests.load(Ordering::SeqCst), 2, "{}", method);
+        }
+    })
+    .await;
+}
+
+#[rocket::async_test]
+async fn requests_timing_out_in_the_queue_do_not_take_the_probe_of_a_half_open_circuit() {
+    let (url, requests) = stub_upstream(&[UNAVAILABLE, OK]).await;
+    let host = url.trim_start_matches("http://").trim_end_matches('/');
+    let host_limit = format!("{}=1", host);
+    let config = config(&[
+        ("CIRCUIT_BREAKER_ENABLED", "true"),
+        ("CIRCUIT_BREAKER_MIN_REQUESTS", "1"),
+        ("CIRCUIT_BREAKER_OPEN_DURATION", "200"),
+        ("HTTP_RETRY_MAX_RETRIES", "0"),
+        ("UPSTREAM_HOST_MAX_CONCURRENT_REQUESTS", &host_limit),
+        ("UPSTREAM_QUEUE_TIMEOUT", "10"),
+    ]);
+    let client = reqwest::Client::new();
+
+    with_config(config, async {
+        // Opens the circuit, which half opens once the open duration is over
+        assert!(send(&client, "GET", &url).await.is_err());
+        sleep(Duration::from_millis(250)).await;
+
+        let permit = concurrency_limit::acquire(host, Duration::from_secs(1))
+            .await
+            .unwrap();
+        let queued = send(&client, "GET", &url).await.unwrap_err();
+        drop(permit);
+        let probe = send(&client, "GET", &url).await;
+
+        assert!(queued.is_upstream_unavailable());
+        assert_eq!(probe.unwrap().body, "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    })
+    .await;
+}
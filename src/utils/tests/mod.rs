@@ -1,6 +1,11 @@
+mod cache_headers;
+mod circuit_breaker;
+mod concurrency_limit;
 mod data_decoded_utils;
 mod errors;
+mod http_client;
 mod json;
 mod macros;
 mod method_names;
+mod retry;
 mod transactions;
@@ -0,0 +1,73 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utils::retry::{is_retryable_status, parse_retry_after, RetryPolicy};
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(250),
+    }
+}
+
+#[test]
+fn delay_grows_exponentially_up_to_max_delay() {
+    let policy = policy();
+
+    for _ in 0..100 {
+        assert!(policy.delay(0, None).unwrap() <= Duration::from_millis(100));
+        assert!(policy.delay(1, None).unwrap() <= Duration::from_millis(200));
+        assert!(policy.delay(2, None).unwrap() <= Duration::from_millis(250));
+    }
+}
+
+#[test]
+fn delay_none_once_retries_are_exhausted() {
+    let policy = policy();
+
+    assert_eq!(policy.delay(3, None), None);
+    assert_eq!(policy.delay(3, Some(Duration::from_secs(1))), None);
+}
+
+#[test]
+fn delay_honours_retry_after() {
+    let actual = policy().delay(0, Some(Duration::from_secs(2)));
+
+    assert_eq!(actual, Some(Duration::from_secs(2)));
+}
+
+#[test]
+fn retryable_statuses() {
+    for status_code in [429, 502, 503, 504] {
+        assert!(is_retryable_status(status_code), "{}", status_code);
+    }
+    for status_code in [200, 400, 404, 422, 500, 501] {
+        assert!(!is_retryable_status(status_code), "{}", status_code);
+    }
+}
+
+#[test]
+fn parse_retry_after_seconds_and_dates() {
+    // Wed, 21 Oct 2015 07:28:00 GMT
+    let date = UNIX_EPOCH + Duration::from_secs(1445412480);
+
+    assert_eq!(
+        parse_retry_after(" 120 ", SystemTime::now()),
+        Some(Duration::from_secs(120))
+    );
+    assert_eq!(
+        parse_retry_after(
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            date - Duration::from_secs(10)
+        ),
+        Some(Duration::from_secs(10))
+    );
+    assert_eq!(
+        parse_retry_after(
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            date + Duration::from_secs(10)
+        ),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon", SystemTime::now()), None);
+}
@@ -1,6 +1,4 @@
-use crate::cache::cache_operations::RequestCached;
 use crate::common::models::backend::transactions::MultisigTransaction;
-use crate::config::transaction_request_timeout;
 use crate::providers::info::{InfoProvider, SAFE_V_1_3_0};
 use crate::utils::errors::ApiResult;
 use ethabi::ethereum_types::H256;
@@ -133,8 +131,9 @@ async fn fetch_cancellation_tx(
     safe_tx_hash: String,
 ) -> Option<MultisigTransaction> {
     let url = core_uri!(info_provider, "/v1/multisig-transactions/{}/", safe_tx_hash).ok()?;
-    let body = RequestCached::new(url, &info_provider.client(), &info_provider.cache())
-        .request_timeout(transaction_request_timeout())
+    let body = info_provider
+        .request(url)
+        .request_timeout(info_provider.config().transaction_request_timeout)
         .execute()
         .await
         .ok();